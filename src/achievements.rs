@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::GameClock,
+    construction::PlacedBuildings,
+    data::JsonAssetAppExt,
+    economy::Economy,
+    interaction::Interacted,
+    map::EnteredInterior,
+    notifications::{Notification, NotificationPriority, Notifications},
+    ui::{PanelSlot, panel_node},
+    upgrades::PurchasedUpgrades,
+};
+
+const ACHIEVEMENT_DEFINITIONS_FILE: &str = "data/sanctuary.achievements.json";
+const TOGGLE_PANEL_KEY: KeyCode = KeyCode::KeyJ;
+
+pub struct AchievementPlugin;
+
+impl Plugin for AchievementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_json_asset::<AchievementDefinitions>(&["achievements.json"])
+            .init_resource::<Counters>()
+            .init_resource::<UnlockedAchievements>()
+            .add_systems(
+                Startup,
+                (load_achievement_definitions, spawn_achievement_panel),
+            )
+            .add_systems(
+                Update,
+                (
+                    rebuild_achievement_catalog,
+                    track_progress_counters,
+                    unlock_achievements.run_if(resource_exists::<AchievementCatalog>),
+                    toggle_achievement_panel,
+                    refresh_achievement_panel.run_if(resource_exists::<AchievementCatalog>),
+                )
+                    .chain(),
+            )
+            .add_observer(count_buildings_entered)
+            .add_observer(count_interactions);
+    }
+}
+
+/// Achievements keyed by id, loaded from a `*.achievements.json` file.
+#[derive(Asset, TypePath, Deserialize)]
+#[serde(transparent)]
+pub struct AchievementDefinitions(pub HashMap<String, AchievementDefinition>);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AchievementDefinition {
+    pub display_name: String,
+    pub description: String,
+    pub icon: Option<String>,
+    pub condition: Condition,
+}
+
+/// When an achievement unlocks, composed from [`Counters`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// The named counter reached `at_least`.
+    Counter {
+        name: String,
+        at_least: u64,
+    },
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    fn is_met(&self, counters: &Counters) -> bool {
+        match self {
+            Condition::Counter { name, at_least } => counters.get(name) >= *at_least,
+            Condition::All(conditions) => conditions.iter().all(|c| c.is_met(counters)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.is_met(counters)),
+        }
+    }
+
+    /// `"current/target"` for single-counter conditions.
+    fn progress(&self, counters: &Counters) -> Option<String> {
+        let Condition::Counter { name, at_least } = self else {
+            return None;
+        };
+        Some(format!(
+            "{}/{}",
+            counters.get(name).min(*at_least),
+            at_least
+        ))
+    }
+}
+
+/// Named game event counters achievements are evaluated against. Saved with the game.
+///
+/// Event counters are incremented as things happen; progress counters (days played, coins,
+/// ...) record the highest value seen so they never go back down.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Counters(HashMap<String, u64>);
+
+impl Counters {
+    pub fn get(&self, name: &str) -> u64 {
+        self.0.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn increment(&mut self, name: &str) {
+        *self.0.entry(name.to_string()).or_default() += 1;
+    }
+
+    /// Raises the counter to `value` if it is higher.
+    pub fn record_max(&mut self, name: &str, value: u64) {
+        if self.get(name) < value {
+            self.0.insert(name.to_string(), value);
+        }
+    }
+}
+
+/// Ids of unlocked achievements. Saved with the game.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UnlockedAchievements(HashSet<String>);
+
+/// Every achievement, available once the definition file has loaded.
+#[derive(Resource)]
+struct AchievementCatalog {
+    achievements: HashMap<String, AchievementDefinition>,
+}
+
+impl AchievementCatalog {
+    /// Achievements sorted by display name, for the panel.
+    fn sorted(&self) -> Vec<(&String, &AchievementDefinition)> {
+        let mut achievements: Vec<_> = self.achievements.iter().collect();
+        achievements.sort_by(|(_, a), (_, b)| a.display_name.cmp(&b.display_name));
+        achievements
+    }
+}
+
+#[derive(Resource)]
+struct AchievementDefinitionsHandle(Handle<AchievementDefinitions>);
+
+#[derive(Component)]
+struct AchievementPanel;
+
+fn load_achievement_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AchievementDefinitionsHandle(
+        asset_server.load(ACHIEVEMENT_DEFINITIONS_FILE),
+    ));
+}
+
+fn rebuild_achievement_catalog(
+    mut commands: Commands,
+    mut asset_events: MessageReader<AssetEvent<AchievementDefinitions>>,
+    handle: Res<AchievementDefinitionsHandle>,
+    definitions: Res<Assets<AchievementDefinitions>>,
+) {
+    let changed = asset_events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. }
+        )
+    });
+    if !changed {
+        return;
+    }
+    let Some(file) = definitions.get(&handle.0) else {
+        return;
+    };
+
+    info!("Achievement catalog loaded: {} achievements", file.0.len());
+    commands.insert_resource(AchievementCatalog {
+        achievements: file.0.clone(),
+    });
+}
+
+/// Counts trips into a building's interior; stepping on its doormat doesn't count.
+fn count_buildings_entered(_trigger: On<EnteredInterior>, mut counters: ResMut<Counters>) {
+    counters.increment("buildings_entered");
+}
+
+fn count_interactions(_trigger: On<Interacted>, mut counters: ResMut<Counters>) {
+    counters.increment("interactions");
+}
+
+/// Mirrors long-running progress into counters.
+fn track_progress_counters(
+    mut counters: ResMut<Counters>,
+    clock: Res<GameClock>,
+    economy: Res<Economy>,
+    purchased: Res<PurchasedUpgrades>,
+    placed: Res<PlacedBuildings>,
+) {
+    // Avoid flagging the counters as changed every frame.
+    let progress = [
+        ("days_played", clock.day() as u64),
+        ("coins", economy.coins() as u64),
+        ("food", economy.food() as u64),
+        ("upgrades_purchased", purchased.count() as u64),
+        ("buildings_placed", placed.0.len() as u64),
+    ];
+    for (name, value) in progress {
+        if counters.get(name) < value {
+            counters.record_max(name, value);
+        }
+    }
+}
+
+fn unlock_achievements(
+    counters: Res<Counters>,
+    catalog: Res<AchievementCatalog>,
+    mut unlocked: ResMut<UnlockedAchievements>,
+    mut notifications: ResMut<Notifications>,
+    asset_server: Res<AssetServer>,
+) {
+    if !counters.is_changed() && !catalog.is_changed() {
+        return;
+    }
+
+    for (id, achievement) in catalog.achievements.iter() {
+        if unlocked.0.contains(id) || !achievement.condition.is_met(&counters) {
+            continue;
+        }
+
+        info!("Achievement unlocked: {}", id);
+        unlocked.0.insert(id.clone());
+
+        let mut notification =
+            Notification::new(format!("Achievement: {}", achievement.display_name))
+                .with_body(achievement.description.clone())
+                .with_priority(NotificationPriority::High);
+        if let Some(icon) = &achievement.icon {
+            notification = notification.with_icon(asset_server.load(icon));
+        }
+        notifications.push(notification);
+    }
+}
+
+fn spawn_achievement_panel(mut commands: Commands) {
+    commands.spawn((
+        AchievementPanel,
+        panel_node(PanelSlot::Left),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+    ));
+}
+
+fn toggle_achievement_panel(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut panel: Single<&mut Node, With<AchievementPanel>>,
+) {
+    if kb_input.just_pressed(TOGGLE_PANEL_KEY) {
+        panel.display = match panel.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+/// Rebuilds the panel's rows when it opens or progress changes while it is open.
+fn refresh_achievement_panel(
+    mut commands: Commands,
+    panel: Single<(Entity, Ref<Node>), With<AchievementPanel>>,
+    catalog: Res<AchievementCatalog>,
+    counters: Res<Counters>,
+    unlocked: Res<UnlockedAchievements>,
+) {
+    let (panel, node) = panel.into_inner();
+    if node.display == Display::None
+        || !(node.is_changed() || catalog.is_changed() || counters.is_changed())
+    {
+        return;
+    }
+
+    commands.entity(panel).despawn_children();
+    commands.entity(panel).with_child(Text::new(format!(
+        "Achievements {}/{} (J to close)",
+        unlocked.0.len(),
+        catalog.achievements.len()
+    )));
+
+    for (id, achievement) in catalog.sorted() {
+        let status = if unlocked.0.contains(id) {
+            "Unlocked".to_string()
+        } else {
+            achievement
+                .condition
+                .progress(&counters)
+                .unwrap_or_else(|| "Locked".to_string())
+        };
+        let color = if unlocked.0.contains(id) {
+            Color::WHITE
+        } else {
+            Color::srgb(0.6, 0.6, 0.6)
+        };
+
+        commands.entity(panel).with_child((
+            Text::new(format!(
+                "{} ({})\n{}",
+                achievement.display_name, status, achievement.description
+            )),
+            TextColor(color),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_tweening::TweeningPlugin;
+
+    use super::*;
+    use crate::{
+        components::{Doorway, TileGroup},
+        interaction::InteractionPlugin,
+        notifications::NotificationPlugin,
+        testing::TestApp,
+    };
+
+    fn interact(app: &mut TestApp) {
+        app.press(KeyCode::KeyE);
+        app.step(1);
+        app.release(KeyCode::KeyE);
+        app.step(1);
+    }
+
+    fn buildings_entered(app: &mut TestApp) -> u64 {
+        app.world().resource::<Counters>().get("buildings_entered")
+    }
+
+    #[test]
+    fn only_trips_inside_count_as_entering_a_building() {
+        let mut app = TestApp::with_plugins(
+            "test/test.world.json",
+            (
+                |app: &mut App| {
+                    app.init_resource::<GameClock>()
+                        .init_resource::<Economy>()
+                        .init_resource::<PurchasedUpgrades>()
+                        .init_resource::<PlacedBuildings>();
+                },
+                TweeningPlugin,
+                NotificationPlugin,
+                InteractionPlugin,
+                AchievementPlugin,
+            ),
+        );
+        app.wait_for_zones(1);
+
+        // Onto the doormat, without going in.
+        app.walk(KeyCode::KeyD, 3);
+        app.walk(KeyCode::KeyW, 3);
+        assert_eq!(buildings_entered(&mut app), 0);
+
+        interact(&mut app);
+        assert_eq!(buildings_entered(&mut app), 1);
+
+        // Back out through the doorway.
+        app.wait_until("doorway", |world| {
+            world
+                .query_filtered::<(), (With<Doorway>, With<TileGroup>)>()
+                .iter(world)
+                .count()
+                == 1
+        });
+        app.walk(KeyCode::KeyS, 1);
+        interact(&mut app);
+        assert_eq!(buildings_entered(&mut app), 1);
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::Counters,
+    buildings::BuildingRegistry,
+    clock::{GameClock, SimulationSeed},
+    collision::CollisionMap,
+    components::{
+        ActorIntentSet, BuildingId, Facing, MoveIntent, MovementCooldown, TileGroup, TilePosition,
+        YSort,
+    },
+    economy::{Cost, Economy},
+    map::HomeMap,
+    weather::{SeeksShelter, Weather, WeatherState},
+};
+
+/// Seconds between two animal steps.
+const ANIMAL_STEP_COOLDOWN: f32 = 0.6;
+/// Chance per tick that an active animal takes a step.
+const WANDER_CHANCE: f64 = 0.05;
+/// How many tiles animals stray from where they were let out.
+const WANDER_RADIUS: u32 = 3;
+/// Side of the placeholder sprite, in pixels.
+const ANIMAL_SIZE: f32 = 20.0;
+/// Hunger gained per in-game hour in mild weather, from 0 (just fed) to 1.
+const HUNGER_PER_HOUR: f32 = 0.05;
+/// Hunger at which the keepers feed an enclosure.
+const HUNGRY: f32 = 0.5;
+/// Food one feeding takes from the pantry.
+const FOOD_PER_MEAL: u32 = 5;
+/// Mixed into the simulation seed so wandering doesn't mirror the weather rolls.
+const WANDER_SEED: u64 = 0xA11A_5EED;
+
+/// The animals of each enclosure: they wander in front of it while active, head for
+/// shelter in bad weather and get hungry faster in the heat.
+pub struct AnimalPlugin;
+
+impl Plugin for AnimalPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AnimalNeeds>()
+            .add_systems(
+                Update,
+                spawn_animals.run_if(resource_exists::<BuildingRegistry>),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    feed_animals,
+                    wander_animals
+                        .in_set(ActorIntentSet)
+                        .run_if(resource_exists::<BuildingRegistry>),
+                ),
+            );
+    }
+}
+
+/// An animal let out in front of its enclosure.
+#[derive(Component, Debug)]
+pub struct Animal {
+    /// Building id of the enclosure the animal belongs to.
+    pub building: String,
+    /// The tile the animal was let out on, which it wanders around.
+    home: TilePosition,
+}
+
+/// Marks an enclosure entrance whose animals have been spawned.
+#[derive(Component)]
+struct AnimalsSpawned;
+
+/// How hungry the animals of each enclosure are, from 0 (just fed) to 1, keyed by
+/// building id. Saved with the game.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AnimalNeeds(BTreeMap<String, f32>);
+
+impl AnimalNeeds {
+    pub fn is_hungry(&self, building: &str) -> bool {
+        self.0.get(building).is_some_and(|hunger| *hunger >= HUNGRY)
+    }
+
+    /// Adds the hunger of `hours` of in-game time in `weather` to every enclosure.
+    fn grow(&mut self, hours: f32, weather: Weather) {
+        let amount = HUNGER_PER_HOUR * weather.need_factor() * hours;
+        for hunger in self.0.values_mut() {
+            *hunger = (*hunger + amount).min(1.0);
+        }
+    }
+}
+
+/// Lets the animals out in front of each enclosure on the home map, one per entrance
+/// tile up to the enclosure's `count`. They are despawned with the home map; their
+/// needs live on in [`AnimalNeeds`].
+fn spawn_animals(
+    mut commands: Commands,
+    registry: Res<BuildingRegistry>,
+    asset_server: Res<AssetServer>,
+    collision_map: Res<CollisionMap>,
+    home: Query<Entity, With<HomeMap>>,
+    parents: Query<&ChildOf>,
+    enclosures: Query<(Entity, &BuildingId, &TileGroup), Without<AnimalsSpawned>>,
+    mut needs: ResMut<AnimalNeeds>,
+) {
+    let Ok(home) = home.single() else {
+        return;
+    };
+
+    for (entrance, building, group) in enclosures.iter() {
+        let Some(definition) = registry.get(&building.id) else {
+            continue;
+        };
+        let Some(animals) = &definition.animals else {
+            continue;
+        };
+        if !parents
+            .iter_ancestors(entrance)
+            .any(|parent| parent == home)
+        {
+            continue;
+        }
+        commands.entity(entrance).insert(AnimalsSpawned);
+        needs.0.entry(building.id.clone()).or_default();
+
+        let tiles = group
+            .tiles()
+            .filter_map(|tile| Facing::Down.step(tile))
+            .filter(|tile| !collision_map.is_blocked(*tile))
+            .take(animals.count as usize);
+        for tile in tiles {
+            commands.spawn((
+                Name::new(animals.name.clone()),
+                Animal {
+                    building: building.id.clone(),
+                    home: tile,
+                },
+                tile,
+                Facing::default(),
+                MovementCooldown(Timer::from_seconds(ANIMAL_STEP_COOLDOWN, TimerMode::Once)),
+                MoveIntent::default(),
+                SeeksShelter::default(),
+                Sprite {
+                    custom_size: Some(Vec2::splat(ANIMAL_SIZE)),
+                    ..Sprite::from_image(asset_server.load(&definition.icon))
+                },
+                Transform::default(),
+                YSort {
+                    foot_offset: -ANIMAL_SIZE / 2.0,
+                },
+                ChildOf(home),
+            ));
+        }
+    }
+}
+
+/// Steps active animals to a random neighbouring tile now and then, staying close to
+/// where they were let out and off the enclosure entrance. Resting animals stay put.
+///
+/// Seeded from the simulation seed so replays see the same animals in the same places.
+pub(crate) fn wander_animals(
+    clock: Res<GameClock>,
+    registry: Res<BuildingRegistry>,
+    seed: Res<SimulationSeed>,
+    collision_map: Res<CollisionMap>,
+    entrances: Query<&TileGroup, With<BuildingId>>,
+    mut animals: Query<(&Animal, &TilePosition, &mut MoveIntent)>,
+    mut rng: Local<Option<ChaCha8Rng>>,
+) {
+    let rng = rng.get_or_insert_with(|| ChaCha8Rng::seed_from_u64(seed.0 ^ WANDER_SEED));
+    let distance = |a: TilePosition, b: TilePosition| a.x.abs_diff(b.x) + a.y.abs_diff(b.y);
+
+    for (animal, position, mut intent) in animals.iter_mut() {
+        intent.0 = None;
+
+        let active = registry
+            .get(&animal.building)
+            .and_then(|definition| definition.animals.as_ref())
+            .is_some_and(|animals| animals.is_active(clock.phase()));
+        if !active || !rng.random_bool(WANDER_CHANCE) {
+            continue;
+        }
+
+        let direction =
+            [Facing::Up, Facing::Down, Facing::Left, Facing::Right][rng.random_range(0..4)];
+        let Some(next) = direction.step(*position) else {
+            continue;
+        };
+        // Past the radius, e.g. after sheltering, only steps back towards home are taken.
+        let strays = distance(next, animal.home) > WANDER_RADIUS
+            && distance(next, animal.home) >= distance(*position, animal.home);
+        let in_doorway = entrances.iter().any(|group| group.contains(next));
+        if strays || in_doorway || collision_map.is_blocked(next) {
+            continue;
+        }
+        intent.0 = Some(direction);
+    }
+}
+
+/// Grows the animals' hunger with the in-game time that passed, faster in the heat, and
+/// has the keepers feed hungry enclosures from the pantry while there is food.
+fn feed_animals(
+    clock: Res<GameClock>,
+    weather: Res<WeatherState>,
+    mut needs: ResMut<AnimalNeeds>,
+    mut economy: ResMut<Economy>,
+    mut counters: ResMut<Counters>,
+    mut last_minutes: Local<Option<f64>>,
+) {
+    let now = clock.total_minutes();
+    let Some(last) = last_minutes.replace(now) else {
+        return;
+    };
+    let hours = (now - last) / 60.0;
+    if hours <= 0.0 {
+        return;
+    }
+
+    needs.grow(hours as f32, weather.current);
+
+    let meal = Cost {
+        food: FOOD_PER_MEAL,
+        ..default()
+    };
+    for (building, hunger) in needs.0.iter_mut() {
+        if *hunger >= HUNGRY && economy.try_spend(meal) {
+            info!("The keepers fed the animals of {}", building);
+            *hunger = 0.0;
+            counters.increment("animals_fed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animals_get_hungry_twice_as_fast_in_the_heat() {
+        let mut needs = AnimalNeeds::default();
+        needs.0.insert("otter_enclosure".to_string(), 0.0);
+
+        needs.grow(6.0, Weather::Sunny);
+        assert!(!needs.is_hungry("otter_enclosure"));
+
+        let mut hot = AnimalNeeds::default();
+        hot.0.insert("otter_enclosure".to_string(), 0.0);
+        hot.grow(6.0, Weather::Heat);
+        assert!(hot.is_hungry("otter_enclosure"));
+    }
+}
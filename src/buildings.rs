@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{clock::DayPhase, components::BuildingId, data::JsonAssetAppExt, economy::Cost};
+
+/// Building definition files merged into the [`BuildingRegistry`], in load order.
+const BUILDING_DEFINITION_FILES: &[&str] = &["data/sanctuary.buildings.json"];
+
+pub struct BuildingPlugin;
+
+impl Plugin for BuildingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_json_asset::<BuildingDefinitions>(&["buildings.json"])
+            .add_systems(Startup, load_building_definitions)
+            .add_systems(Update, rebuild_building_registry);
+    }
+}
+
+/// Building definitions keyed by building id, loaded from a `*.buildings.json` file.
+#[derive(Asset, TypePath, Deserialize)]
+#[serde(transparent)]
+pub struct BuildingDefinitions(pub HashMap<String, BuildingDefinition>);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BuildingDefinition {
+    pub display_name: String,
+    pub icon: String,
+    pub description: String,
+    pub opening_hours: OpeningHours,
+    /// Present for buildings the player can place in build mode.
+    pub construction: Option<Construction>,
+    /// Present for enclosures, whose animals follow the clock.
+    pub animals: Option<AnimalActivity>,
+}
+
+/// When the animals of an enclosure are out and about.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnimalActivity {
+    /// How the building details name the animals, e.g. "The otters".
+    pub name: String,
+    /// Parts of the day the animals are active in; they rest the rest of the time.
+    pub active: Vec<DayPhase>,
+    /// Animals let out in front of the enclosure, at most one per entrance tile.
+    pub count: u32,
+}
+
+impl AnimalActivity {
+    pub fn is_active(&self, phase: DayPhase) -> bool {
+        self.active.contains(&phase)
+    }
+}
+
+/// How a building is placed in build mode.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Construction {
+    /// Width and height in tiles of the solid footprint. The entrance zone is the row of
+    /// tiles right below it.
+    pub footprint: [u32; 2],
+    pub cost: Cost,
+}
+
+/// Opening hours in whole in-game hours, `open` inclusive and `close` exclusive.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct OpeningHours {
+    pub open: u32,
+    pub close: u32,
+}
+
+impl OpeningHours {
+    /// Whether the building is open during `hour`. Hours past midnight (`close < open`)
+    /// wrap around.
+    pub fn is_open(self, hour: u32) -> bool {
+        if self.open <= self.close {
+            (self.open..self.close).contains(&hour)
+        } else {
+            hour >= self.open || hour < self.close
+        }
+    }
+}
+
+/// All known buildings, merged from every definition file.
+///
+/// Only inserted once every file has loaded, so systems can use
+/// `resource_exists::<BuildingRegistry>` to wait for it.
+#[derive(Resource, Default)]
+pub struct BuildingRegistry {
+    buildings: HashMap<String, BuildingDefinition>,
+}
+
+impl BuildingRegistry {
+    pub fn get(&self, id: &str) -> Option<&BuildingDefinition> {
+        self.buildings.get(id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.buildings.keys().map(String::as_str)
+    }
+
+    /// Buildings that can be placed in build mode, sorted by id for a stable order.
+    pub fn constructible(&self) -> Vec<(&str, &BuildingDefinition)> {
+        let mut buildings: Vec<_> = self
+            .buildings
+            .iter()
+            .filter(|(_, definition)| definition.construction.is_some())
+            .map(|(id, definition)| (id.as_str(), definition))
+            .collect();
+        buildings.sort_by_key(|(id, _)| *id);
+        buildings
+    }
+}
+
+#[derive(Resource)]
+struct BuildingDefinitionFiles(Vec<Handle<BuildingDefinitions>>);
+
+fn load_building_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BuildingDefinitionFiles(
+        BUILDING_DEFINITION_FILES
+            .iter()
+            .map(|path| asset_server.load(*path))
+            .collect(),
+    ));
+}
+
+/// (Re)builds the registry whenever a definition file finishes loading or changes.
+///
+/// A reloaded registry that lacks a building referenced in the world is rejected and
+/// the previous one kept, so a bad edit doesn't take down a running game.
+fn rebuild_building_registry(
+    mut commands: Commands,
+    mut asset_events: MessageReader<AssetEvent<BuildingDefinitions>>,
+    files: Res<BuildingDefinitionFiles>,
+    definitions: Res<Assets<BuildingDefinitions>>,
+    previous: Option<Res<BuildingRegistry>>,
+    referenced: Query<&BuildingId>,
+) {
+    let changed = asset_events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. }
+        )
+    });
+    if !changed {
+        return;
+    }
+
+    let mut registry = BuildingRegistry::default();
+    for handle in files.0.iter() {
+        let Some(file) = definitions.get(handle) else {
+            // Wait until every file is available so lookups never see a partial registry.
+            return;
+        };
+        for (id, definition) in file.0.iter() {
+            if registry
+                .buildings
+                .insert(id.clone(), definition.clone())
+                .is_some()
+            {
+                warn!("Building '{}' is defined more than once, last one wins", id);
+            }
+        }
+    }
+
+    if previous.is_some()
+        && let Some(missing) = referenced
+            .iter()
+            .find(|building| registry.get(&building.id).is_none())
+    {
+        error!(
+            "Reloaded building definitions lack building '{}', keeping the previous ones",
+            missing.id
+        );
+        return;
+    }
+
+    info!(
+        "Building registry loaded: {} buildings",
+        registry.buildings.len()
+    );
+    commands.insert_resource(registry);
+}
@@ -1,27 +1,76 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
-use crate::components::{Player, PlayerMovementSet};
+use crate::{
+    clock::{GameClock, MINUTES_PER_DAY},
+    components::{Player, PlayerMovementSet},
+};
 
-const CAMERA_DECAY_RATE: f32 = 2.0;
+/// Large enough to cover the view at any zoom we use.
+const DAYLIGHT_OVERLAY_SIZE: f32 = 8192.0;
+
+/// Overlay tint through the day as `(minute of day, color)`, interpolated linearly and
+/// wrapping around midnight.
+const DAYLIGHT_KEYFRAMES: &[(u32, Srgba)] = &[
+    (0, Srgba::new(0.05, 0.07, 0.25, 0.55)),
+    (5 * 60, Srgba::new(0.05, 0.07, 0.25, 0.55)),
+    (6 * 60, Srgba::new(0.95, 0.6, 0.4, 0.2)),
+    (8 * 60, Srgba::new(1.0, 1.0, 1.0, 0.0)),
+    (17 * 60, Srgba::new(1.0, 1.0, 1.0, 0.0)),
+    (19 * 60, Srgba::new(0.95, 0.45, 0.25, 0.25)),
+    (21 * 60, Srgba::new(0.05, 0.07, 0.25, 0.55)),
+];
 
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_camera)
-            .add_systems(Update, update_camera.after(PlayerMovementSet));
+        app.init_resource::<CameraConfig>()
+            .add_systems(Startup, setup_camera)
+            .add_systems(
+                Update,
+                (
+                    update_camera.after(PlayerMovementSet),
+                    update_daylight_overlay,
+                ),
+            );
+    }
+}
+
+/// Camera tuning, overridable from the settings file.
+#[derive(Resource, Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraConfig {
+    /// How quickly the camera catches up with the player; higher is snappier.
+    pub decay_rate: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self { decay_rate: 2.0 }
     }
 }
 
+/// Full-view sprite on the camera tinting the world by time of day. UI is drawn on top.
+#[derive(Component)]
+struct DaylightOverlay;
+
 fn setup_camera(mut commands: Commands, mut window: Single<&mut Window>) {
     window.resolution.set(1080., 1920.);
-    commands.spawn(Camera2d);
+    commands.spawn(Camera2d).with_child((
+        DaylightOverlay,
+        Sprite::from_color(Color::NONE, Vec2::splat(DAYLIGHT_OVERLAY_SIZE)),
+        // In front of every world sprite, which stay at or below z 950, and still inside the
+        // 2D camera's view volume, which reaches z 1000.
+        Transform::from_xyz(0.0, 0.0, 999.0),
+    ));
 }
 
 /// Smoothly tracks the player with the camera.
 fn update_camera(
     mut camera: Single<&mut Transform, (With<Camera2d>, Without<Player>)>,
     player: Single<&Transform, (With<Player>, Without<Camera2d>)>,
+    config: Res<CameraConfig>,
     time: Res<Time>,
 ) {
     let target = Vec3::new(
@@ -32,5 +81,36 @@ fn update_camera(
 
     camera
         .translation
-        .smooth_nudge(&target, CAMERA_DECAY_RATE, time.delta_secs());
+        .smooth_nudge(&target, config.decay_rate, time.delta_secs());
+}
+
+fn daylight_tint(minute_of_day: f32) -> Srgba {
+    let next = DAYLIGHT_KEYFRAMES
+        .iter()
+        .position(|(minute, _)| *minute as f32 > minute_of_day)
+        .unwrap_or(0);
+    let previous = next.checked_sub(1).unwrap_or(DAYLIGHT_KEYFRAMES.len() - 1);
+
+    let (start, from) = DAYLIGHT_KEYFRAMES[previous];
+    let (end, to) = DAYLIGHT_KEYFRAMES[next];
+    let span = (end + MINUTES_PER_DAY - start) % MINUTES_PER_DAY;
+    let elapsed = (minute_of_day - start as f32).rem_euclid(MINUTES_PER_DAY as f32);
+    let factor = if span == 0 {
+        0.0
+    } else {
+        elapsed / span as f32
+    };
+
+    from.mix(&to, factor)
+}
+
+fn update_daylight_overlay(
+    clock: Res<GameClock>,
+    mut overlay: Single<&mut Sprite, With<DaylightOverlay>>,
+) {
+    let minute_of_day = (clock.total_minutes() % MINUTES_PER_DAY as f64) as f32;
+    let color = Color::from(daylight_tint(minute_of_day));
+    if overlay.color != color {
+        overlay.color = color;
+    }
 }
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, de::Error};
+
+use crate::components::SyntheticInputSet;
+
+pub const MINUTES_PER_DAY: u32 = 24 * 60;
+/// The sanctuary opens its first day at 06:00.
+const START_MINUTE: f64 = 6.0 * 60.0;
+/// In-game minutes that pass per real second.
+const DEFAULT_MINUTES_PER_SECOND: f64 = 1.0;
+/// Upper bound for time acceleration.
+const MAX_TIME_SCALE: f64 = 64.0;
+/// Simulation ticks per real second, see [`FixedUpdate`].
+const SIMULATION_HZ: f64 = 64.0;
+
+const SPEED_UP_KEY: KeyCode = KeyCode::BracketRight;
+const SLOW_DOWN_KEY: KeyCode = KeyCode::BracketLeft;
+const RESET_SPEED_KEY: KeyCode = KeyCode::Backslash;
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameClock>()
+            .insert_resource(SimulationSeed(rand::random()))
+            .insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+            .add_systems(PreUpdate, adjust_time_scale.after(SyntheticInputSet))
+            .add_systems(FixedPreUpdate, (tick_clock, announce_new_days).chain());
+    }
+}
+
+/// Seed every simulation RNG derives from, so a run can be reproduced. Random for a new
+/// game and restored from the save otherwise.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct SimulationSeed(pub u64);
+
+/// How far the clock was moved forward on load to cover the time the game was closed.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct OfflineCatchUp {
+    pub minutes: f64,
+}
+
+/// Triggered once for every in-game day that starts, including days skipped over by a
+/// large clock jump.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DayStarted {
+    pub day: u32,
+}
+
+/// Coarse part of the day, for lighting and anything that behaves differently at night.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DayPhase {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl DayPhase {
+    pub fn name(self) -> &'static str {
+        match self {
+            DayPhase::Dawn => "Dawn",
+            DayPhase::Day => "Day",
+            DayPhase::Dusk => "Dusk",
+            DayPhase::Night => "Night",
+        }
+    }
+}
+
+/// In-game time, advanced by the simulation.
+#[derive(Resource)]
+pub struct GameClock {
+    total_minutes: f64,
+    pub minutes_per_second: f64,
+    /// Multiplier on [`Self::minutes_per_second`], changed with the speed keys for testing.
+    pub time_scale: f64,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self {
+            total_minutes: START_MINUTE,
+            minutes_per_second: DEFAULT_MINUTES_PER_SECOND,
+            time_scale: 1.0,
+        }
+    }
+}
+
+impl GameClock {
+    /// Minutes since midnight of day 1.
+    pub fn total_minutes(&self) -> f64 {
+        self.total_minutes
+    }
+
+    /// 1-based day counter.
+    pub fn day(&self) -> u32 {
+        (self.total_minutes / MINUTES_PER_DAY as f64) as u32 + 1
+    }
+
+    pub fn minute_of_day(&self) -> u32 {
+        (self.total_minutes as u64 % MINUTES_PER_DAY as u64) as u32
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.minute_of_day() / 60
+    }
+
+    pub fn phase(&self) -> DayPhase {
+        match self.hour() {
+            5..7 => DayPhase::Dawn,
+            7..18 => DayPhase::Day,
+            18..21 => DayPhase::Dusk,
+            _ => DayPhase::Night,
+        }
+    }
+
+    /// The time of day as `"HH:MM"`.
+    pub fn time_of_day_text(&self) -> String {
+        let minute = self.minute_of_day();
+        format!("{:02}:{:02}", minute / 60, minute % 60)
+    }
+
+    /// Jumps straight to a saved point in time.
+    pub fn restore(&mut self, total_minutes: f64) {
+        self.total_minutes = total_minutes.max(0.0);
+    }
+
+    /// Moves time forward, e.g. to catch up on time spent offline.
+    pub fn advance(&mut self, minutes: f64) {
+        self.total_minutes += minutes.max(0.0);
+    }
+}
+
+fn adjust_time_scale(mut clock: ResMut<GameClock>, kb_input: Res<ButtonInput<KeyCode>>) {
+    let time_scale = if kb_input.just_pressed(SPEED_UP_KEY) {
+        (clock.time_scale * 2.0).min(MAX_TIME_SCALE)
+    } else if kb_input.just_pressed(SLOW_DOWN_KEY) {
+        (clock.time_scale / 2.0).max(1.0)
+    } else if kb_input.just_pressed(RESET_SPEED_KEY) {
+        1.0
+    } else {
+        return;
+    };
+
+    if clock.time_scale != time_scale {
+        info!("Time scale: x{}", time_scale);
+        clock.time_scale = time_scale;
+    }
+}
+
+pub(crate) fn tick_clock(mut clock: ResMut<GameClock>, time: Res<Time>) {
+    let minutes = time.delta_secs_f64() * clock.minutes_per_second * clock.time_scale;
+    clock.advance(minutes);
+}
+
+fn announce_new_days(
+    clock: Res<GameClock>,
+    mut last_day: Local<Option<u32>>,
+    mut commands: Commands,
+) {
+    let day = clock.day();
+    let Some(previous) = last_day.replace(day) else {
+        // The day the game starts on counts as started too.
+        commands.trigger(DayStarted { day });
+        return;
+    };
+
+    for day in previous + 1..=day {
+        info!("Day {} started", day);
+        commands.trigger(DayStarted { day });
+    }
+}
+
+/// Parses `"HH:MM"` into minutes since midnight.
+pub fn parse_time_of_day(text: &str) -> Option<u32> {
+    let (hours, minutes) = text.split_once(':')?;
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Serde helper for `"HH:MM"` fields in data files.
+pub fn deserialize_time_of_day<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u32, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    parse_time_of_day(&text)
+        .ok_or_else(|| D::Error::custom(format!("invalid time of day '{}', expected HH:MM", text)))
+}
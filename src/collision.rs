@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::{
+    components::{PlayerMovementSet, Solid, TileGroup, TilePosition, TileRect},
+    entrance::zone_tile_group,
+    map::MapGrids,
+};
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Solid>()
+            .init_resource::<CollisionMap>()
+            .add_systems(
+                Update,
+                (snap_solids, rebuild_collision_map)
+                    .chain()
+                    .before(PlayerMovementSet),
+            );
+    }
+}
+
+/// Tiles actors cannot step onto, rebuilt from every [`Solid`] [`TileGroup`].
+#[derive(Resource, Default)]
+pub struct CollisionMap {
+    blocked: HashSet<TilePosition>,
+}
+
+impl CollisionMap {
+    pub fn is_blocked(&self, position: TilePosition) -> bool {
+        self.blocked.contains(&position)
+    }
+
+    /// Blocked tiles, for the debug overlay.
+    #[cfg(debug_assertions)]
+    pub fn tiles(&self) -> impl Iterator<Item = TilePosition> + '_ {
+        self.blocked.iter().copied()
+    }
+}
+
+fn snap_solids(
+    mut commands: Commands,
+    grids: MapGrids,
+    query: Query<
+        (
+            Entity,
+            Option<&Transform>,
+            Option<&TiledObject>,
+            Option<&TileRect>,
+        ),
+        (
+            With<Solid>,
+            Or<(With<TiledObject>, With<TileRect>)>,
+            Without<TileGroup>,
+        ),
+    >,
+) {
+    for (entity, transform, tiled_object, rect) in query.iter() {
+        let label = format!("Solid {:?}", entity);
+        let grid = grids.of(entity);
+        if let Some(tile_group) = zone_tile_group(&label, grid, transform, tiled_object, rect) {
+            commands.entity(entity).insert(tile_group);
+        }
+    }
+}
+
+fn rebuild_collision_map(
+    mut collision_map: ResMut<CollisionMap>,
+    solids: Query<&TileGroup, With<Solid>>,
+    changed: Query<(), (With<Solid>, Changed<TileGroup>)>,
+    mut removed: RemovedComponents<Solid>,
+) {
+    let removed_any = removed.read().count() > 0;
+    if changed.is_empty() && !removed_any {
+        return;
+    }
+
+    collision_map.blocked = solids.iter().flat_map(TileGroup::tiles).collect();
+}
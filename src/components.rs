@@ -1,23 +1,42 @@
 use bevy::prelude::*;
 
-pub const TILE_SIZE: u32 = 32;
-pub const PLAYER_Z_INDEX: f32 = 2.0;
+/// Depth assigned to a y-sorted entity whose foot sits at `y = 0`.
+pub const Y_SORT_BASE_Z: f32 = 500.0;
+/// How much depth is removed per world pixel of foot height.
+pub const Y_SORT_Z_PER_PIXEL: f32 = 0.001;
 
+/// Systems deciding where actors want to step this tick (player input, NPC AI), in
+/// `FixedUpdate`.
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct ActorIntentSet;
+
+/// Grid movement of the player and every other actor: the steps themselves in
+/// `FixedUpdate`, and the interpolated transforms in `Update`.
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub struct PlayerMovementSet;
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub struct PlayerSetupSet;
 
+/// Systems feeding recorded input into `ButtonInput` in `PreUpdate`. Input read in
+/// `PreUpdate` must run after it.
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct SyntheticInputSet;
+
 #[derive(Component)]
 pub struct Player;
 
-#[derive(Component, Clone, Copy, Debug)]
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct TilePosition {
     pub x: u32,
     pub y: u32,
 }
 
+/// Marks the map the player is on, whose [`TileGrid`](crate::grid::TileGrid) actors move
+/// and build on.
+#[derive(Component, Debug, Default)]
+pub struct ActiveMap;
+
 #[derive(Component, Debug)]
 pub enum TileGroup {
     Rectangle(RectangleTileGroup),
@@ -29,16 +48,227 @@ pub struct RectangleTileGroup {
     pub top_right: TilePosition,
 }
 
+impl TileGroup {
+    /// Every tile covered by the group.
+    pub fn tiles(&self) -> impl Iterator<Item = TilePosition> + '_ {
+        match self {
+            TileGroup::Rectangle(rect) => {
+                (rect.bottom_left.y..=rect.top_right.y).flat_map(move |y| {
+                    (rect.bottom_left.x..=rect.top_right.x).map(move |x| TilePosition { x, y })
+                })
+            }
+        }
+    }
+
+    pub fn contains(&self, position: TilePosition) -> bool {
+        match self {
+            TileGroup::Rectangle(rect) => {
+                position.x >= rect.bottom_left.x
+                    && position.x <= rect.top_right.x
+                    && position.y >= rect.bottom_left.y
+                    && position.y <= rect.top_right.y
+            }
+        }
+    }
+}
+
+/// A zone defined in tile coordinates from Rust rather than as a Tiled object.
+///
+/// Converted into a [`TileGroup`] by the same systems that snap Tiled zones.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct TileRect {
+    pub bottom_left: TilePosition,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl TileRect {
+    pub fn new(bottom_left: TilePosition, width: u32, height: u32) -> Self {
+        Self {
+            bottom_left,
+            width,
+            height,
+        }
+    }
+
+    /// Every tile covered by the rect.
+    pub fn tiles(self) -> impl Iterator<Item = TilePosition> {
+        let TilePosition { x, y } = self.bottom_left;
+        (y..y + self.height)
+            .flat_map(move |y| (x..x + self.width).map(move |x| TilePosition { x, y }))
+    }
+}
+
 #[derive(Component)]
 pub struct MovementCooldown(pub Timer);
 
+/// Direction an actor wants to step in, consumed by the shared movement pipeline.
+#[derive(Component, Debug, Default)]
+#[require(PreviousTilePosition)]
+pub struct MoveIntent(pub Option<Facing>);
+
+/// Where an actor stood before the last simulation tick, to interpolate its transform
+/// between ticks. `None` until the first tick.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PreviousTilePosition(pub Option<TilePosition>);
+
+/// Marks a Tiled rectangle whose tiles cannot be walked on.
+#[derive(Component, Debug, Reflect, Default, Clone, Copy)]
+#[reflect(Component, Default)]
+pub struct Solid;
+
+/// Marks a Tiled rectangle actors retreat to in bad weather.
+#[derive(Component, Debug, Reflect, Default, Clone, Copy)]
+#[reflect(Component, Default)]
+pub struct Shelter;
+
+/// Direction of the last grid step taken by an actor.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Facing {
+    Up,
+    #[default]
+    Down,
+    Left,
+    Right,
+}
+
+impl Facing {
+    /// The tile one step in this direction, if it is still on the grid.
+    pub fn step(self, position: TilePosition) -> Option<TilePosition> {
+        let TilePosition { x, y } = position;
+        let (x, y) = match self {
+            Facing::Up => (Some(x), y.checked_add(1)),
+            Facing::Down => (Some(x), y.checked_sub(1)),
+            Facing::Left => (x.checked_sub(1), Some(y)),
+            Facing::Right => (x.checked_add(1), Some(y)),
+        };
+        Some(TilePosition { x: x?, y: y? })
+    }
+
+    pub fn opposite(self) -> Facing {
+        match self {
+            Facing::Up => Facing::Down,
+            Facing::Down => Facing::Up,
+            Facing::Left => Facing::Right,
+            Facing::Right => Facing::Left,
+        }
+    }
+
+    /// The direction of a single grid step from `from` to the adjacent tile `to`.
+    pub fn towards(from: TilePosition, to: TilePosition) -> Option<Facing> {
+        [Facing::Up, Facing::Down, Facing::Left, Facing::Right]
+            .into_iter()
+            .find(|direction| direction.step(from) == Some(to))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Facing::Up => "up",
+            Facing::Down => "down",
+            Facing::Left => "left",
+            Facing::Right => "right",
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct DisplayCurrentTile;
 
-#[derive(Component, Debug, Reflect, Default, Clone, Copy)]
+#[derive(Component)]
+pub struct DisplayClock;
+
+#[derive(Component)]
+pub struct DisplayEconomy;
+
+/// The building entrance zone an actor is currently standing in.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct InEntrance(pub Entity);
+
+/// Marks a zone or NPC the player can interact with, e.g. `"enter Nutrition House"`.
+///
+/// The prompt is shown after the input hint: "Press E to {prompt}".
+#[derive(Component, Debug, Reflect, Default, Clone)]
+#[reflect(Component, Default)]
+pub struct Interactable {
+    pub prompt: String,
+}
+
+/// Starts a `*.dialogue.json` script when the player interacts with the zone or NPC, e.g.
+/// a sign. With `on_enter` it also starts when the player steps into the zone.
+#[derive(Component, Debug, Reflect, Default, Clone)]
+#[reflect(Component, Default)]
+pub struct Dialogue {
+    pub script: String,
+    pub on_enter: bool,
+}
+
+/// A zone leading to another map, e.g. the door out of an interior. Where it leads is
+/// declared by the world manifest's links, matched by `id`.
+#[derive(Component, Debug, Reflect, Default, Clone)]
+#[reflect(Component, Default)]
+pub struct Doorway {
+    pub id: String,
+}
+
+/// The interactable zone or NPC under or in front of an actor, if any.
+#[derive(Component, Debug, Default)]
+pub struct InteractionTarget(pub Option<Entity>);
+
+/// Core buildings known at compile time, exported to Tiled as a type-safe enum.
+#[derive(Component, Debug, Reflect, Default, Clone, Copy, PartialEq, Eq, Hash)]
 #[reflect(Component, Default)]
 pub enum BuildingEntrance {
     #[default]
     NoEntrance,
     NutritionHouse,
 }
+
+impl BuildingEntrance {
+    /// Registry id of a core building, `None` for [`BuildingEntrance::NoEntrance`].
+    pub fn building_id(self) -> Option<BuildingId> {
+        match self {
+            BuildingEntrance::NoEntrance => None,
+            BuildingEntrance::NutritionHouse => Some(BuildingId::new("nutrition_house")),
+        }
+    }
+}
+
+/// Reference to a building in the [`BuildingRegistry`](crate::buildings::BuildingRegistry).
+///
+/// Data-only buildings are placed in Tiled with this property instead of [`BuildingEntrance`].
+#[derive(Component, Debug, Reflect, Default, Clone, PartialEq, Eq, Hash)]
+#[reflect(Component, Default)]
+pub struct BuildingId {
+    pub id: String,
+}
+
+impl BuildingId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into() }
+    }
+}
+
+/// Depth-sorts an entity by the world Y of its foot/anchor point.
+///
+/// `foot_offset` is added to the entity's translation to find the foot (e.g. `-16.0` for a
+/// sprite centered on a 32px tile). Tiled tile objects are anchored at their bottom edge,
+/// so they usually keep the default of `0.0`.
+#[derive(Component, Debug, Reflect, Default, Clone, Copy)]
+#[reflect(Component, Default)]
+pub struct YSort {
+    pub foot_offset: f32,
+}
+
+/// Fades a y-sorted building's sprites while the player walks behind it.
+#[derive(Component, Debug, Reflect, Clone, Copy)]
+#[reflect(Component, Default)]
+pub struct OcclusionFade {
+    /// Alpha applied to the building while the player is occluded.
+    pub alpha: f32,
+}
+
+impl Default for OcclusionFade {
+    fn default() -> Self {
+        Self { alpha: 0.4 }
+    }
+}
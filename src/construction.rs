@@ -0,0 +1,366 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buildings::{BuildingDefinition, BuildingRegistry, Construction},
+    collision::CollisionMap,
+    components::{ActiveMap, BuildingId, Solid, TileGroup, TilePosition, TileRect, YSort},
+    economy::Economy,
+    entrance::SpawnZoneExt,
+    grid::TileGrid,
+    map::HomeMap,
+    notifications::{CategoryMode, Notification, Notifications},
+    upgrades::{PurchasedUpgrades, UpgradeCatalog},
+};
+
+const TOGGLE_BUILD_MODE_KEY: KeyCode = KeyCode::KeyB;
+const NEXT_BUILDING_KEY: KeyCode = KeyCode::Tab;
+const BUILD_MODE_CATEGORY: &str = "build_mode";
+/// The placement preview sits above weather particles but below the daylight overlay.
+const GHOST_Z: f32 = 950.0;
+const VALID_TINT: Color = Color::srgba(0.4, 1.0, 0.4, 0.5);
+const INVALID_TINT: Color = Color::srgba(1.0, 0.3, 0.3, 0.5);
+
+pub struct ConstructionPlugin;
+
+impl Plugin for ConstructionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BuildMode>()
+            .init_resource::<PlacedBuildings>()
+            .add_systems(Startup, spawn_build_ghost)
+            .add_systems(
+                Update,
+                (
+                    (
+                        toggle_build_mode,
+                        cycle_selected_building,
+                        update_build_ghost,
+                        place_building,
+                    )
+                        .chain()
+                        .run_if(resource_exists::<UpgradeCatalog>),
+                    spawn_placed_buildings,
+                )
+                    .chain()
+                    .run_if(resource_exists::<BuildingRegistry>),
+            );
+    }
+}
+
+/// A building placed in build mode. Saved with the game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacedBuilding {
+    pub id: String,
+    /// Bottom-left tile of the footprint.
+    pub tile: [u32; 2],
+}
+
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PlacedBuildings(pub Vec<PlacedBuilding>);
+
+#[derive(Resource, Default)]
+struct BuildMode {
+    active: bool,
+    /// Index into the buildings that can currently be placed.
+    selected: usize,
+    /// Building and footprint origin under the cursor, if it can be placed there.
+    hover: Option<(String, TilePosition)>,
+}
+
+/// Translucent preview of the footprint and entrance row under the cursor.
+#[derive(Component)]
+struct BuildGhost;
+
+/// The zones a building covers when its footprint starts at `origin`: the solid footprint
+/// and the entrance row right below it. `None` if the entrance would fall off the map.
+fn placement_rects(origin: TilePosition, construction: &Construction) -> Option<[TileRect; 2]> {
+    let [width, height] = construction.footprint;
+    let entrance_y = origin.y.checked_sub(1)?;
+
+    let footprint = TileRect::new(origin, width, height);
+    let entrance = TileRect::new(
+        TilePosition {
+            x: origin.x,
+            y: entrance_y,
+        },
+        width,
+        1,
+    );
+    Some([footprint, entrance])
+}
+
+#[derive(SystemParam)]
+struct Unlocks<'w> {
+    registry: Res<'w, BuildingRegistry>,
+    catalog: Res<'w, UpgradeCatalog>,
+    purchased: Res<'w, PurchasedUpgrades>,
+}
+
+impl Unlocks<'_> {
+    /// Buildings the player may place right now, in a stable order.
+    fn buildable(&self) -> Vec<(&str, &BuildingDefinition)> {
+        self.registry
+            .constructible()
+            .into_iter()
+            .filter(|(id, _)| self.catalog.is_building_unlocked(id, &self.purchased))
+            .collect()
+    }
+}
+
+fn spawn_build_ghost(mut commands: Commands) {
+    commands.spawn((
+        BuildGhost,
+        Sprite::from_color(VALID_TINT, Vec2::ONE),
+        Transform::default(),
+        Visibility::Hidden,
+    ));
+}
+
+fn show_selection(
+    notifications: &mut Notifications,
+    buildable: &[(&str, &BuildingDefinition)],
+    selected: usize,
+) {
+    let (_, definition) = buildable[selected];
+    let cost = definition
+        .construction
+        .map(|construction| construction.cost)
+        .unwrap_or_default();
+    notifications.push(
+        Notification::new(format!("Build: {}", definition.display_name))
+            .with_body(format!(
+                "Costs {}\nClick to place, Tab for next, B to exit",
+                cost
+            ))
+            .with_category(BUILD_MODE_CATEGORY, CategoryMode::Replace)
+            .sticky(),
+    );
+}
+
+fn toggle_build_mode(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut build_mode: ResMut<BuildMode>,
+    unlocks: Unlocks,
+    mut notifications: ResMut<Notifications>,
+) {
+    if !kb_input.just_pressed(TOGGLE_BUILD_MODE_KEY) {
+        return;
+    }
+
+    if build_mode.active {
+        leave_build_mode(&mut build_mode, &mut notifications);
+        return;
+    }
+
+    let buildable = unlocks.buildable();
+    if buildable.is_empty() {
+        notifications.push(
+            Notification::new("Nothing to build yet")
+                .with_body("Buy upgrades to unlock buildings."),
+        );
+        return;
+    }
+
+    info!("Entering build mode");
+    build_mode.active = true;
+    build_mode.selected %= buildable.len();
+    show_selection(&mut notifications, &buildable, build_mode.selected);
+}
+
+fn leave_build_mode(build_mode: &mut BuildMode, notifications: &mut Notifications) {
+    info!("Leaving build mode");
+    build_mode.active = false;
+    notifications.dismiss_category(BUILD_MODE_CATEGORY);
+}
+
+/// Selects the next building on Tab. Leaves build mode if nothing can be built anymore,
+/// e.g. after the catalogs were reloaded.
+fn cycle_selected_building(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut build_mode: ResMut<BuildMode>,
+    unlocks: Unlocks,
+    mut notifications: ResMut<Notifications>,
+) {
+    if !build_mode.active {
+        return;
+    }
+    let buildable = unlocks.buildable();
+    if buildable.is_empty() {
+        leave_build_mode(&mut build_mode, &mut notifications);
+        return;
+    }
+    if !kb_input.just_pressed(NEXT_BUILDING_KEY) {
+        return;
+    }
+
+    build_mode.selected = (build_mode.selected + 1) % buildable.len();
+    show_selection(&mut notifications, &buildable, build_mode.selected);
+}
+
+/// Snaps the preview to the tile under the cursor and tints it by whether the building
+/// fits there.
+#[allow(clippy::too_many_arguments)]
+fn update_build_ghost(
+    mut build_mode: ResMut<BuildMode>,
+    unlocks: Unlocks,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    collision_map: Res<CollisionMap>,
+    home: Query<&TileGrid, (With<HomeMap>, With<ActiveMap>)>,
+    zones: Query<&TileGroup>,
+    actors: Query<&TilePosition>,
+    ghost: Single<(&mut Sprite, &mut Transform, &mut Visibility), With<BuildGhost>>,
+) {
+    let (mut sprite, mut transform, mut visibility) = ghost.into_inner();
+    build_mode.hover = None;
+
+    let buildable = unlocks.buildable();
+    let selected = buildable
+        .get(build_mode.selected)
+        .and_then(|(id, definition)| Some((*id, definition.construction?)));
+    let (camera, camera_transform) = *camera;
+    let cursor = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+        .zip(home.single().ok())
+        .and_then(|(world, grid)| Some((grid.world_to_tile(world)?, grid)));
+
+    let (Some((id, construction)), Some((origin, grid)), true) =
+        (selected, cursor, build_mode.active)
+    else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+
+    let rects = placement_rects(origin, &construction);
+    let valid = rects.is_some_and(|rects| {
+        rects.into_iter().flat_map(TileRect::tiles).all(|tile| {
+            !collision_map.is_blocked(tile)
+                && !zones.iter().any(|zone| zone.contains(tile))
+                && !actors.iter().any(|actor| *actor == tile)
+        })
+    });
+    if valid {
+        build_mode.hover = Some((id.to_string(), origin));
+    }
+
+    // Cover the footprint plus the entrance row below it.
+    let [width, height] = construction.footprint;
+    let bounds = match rects {
+        Some([footprint, entrance]) => grid
+            .rect_bounds(footprint)
+            .union(grid.rect_bounds(entrance)),
+        None => grid.rect_bounds(TileRect::new(origin, width, height)),
+    };
+
+    sprite.custom_size = Some(bounds.size());
+    sprite.color = if valid { VALID_TINT } else { INVALID_TINT };
+    transform.translation = bounds.center().extend(GHOST_Z);
+    visibility.set_if_neq(Visibility::Visible);
+}
+
+/// Places the hovered building on click, unless the click went to a UI button.
+fn place_building(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    build_mode: Res<BuildMode>,
+    registry: Res<BuildingRegistry>,
+    ui: Query<&Interaction>,
+    mut economy: ResMut<Economy>,
+    mut placed: ResMut<PlacedBuildings>,
+    mut notifications: ResMut<Notifications>,
+) {
+    if !build_mode.active || !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    if ui
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+    let Some((id, origin)) = &build_mode.hover else {
+        return;
+    };
+    let Some(definition) = registry.get(id) else {
+        return;
+    };
+    let Some(construction) = definition.construction else {
+        return;
+    };
+
+    if !economy.try_spend(construction.cost) {
+        notifications.push(
+            Notification::new(definition.display_name.clone())
+                .with_body(format!("Costs {}", construction.cost)),
+        );
+        return;
+    }
+
+    info!("Placing {} at {:?}", id, origin);
+    placed.0.push(PlacedBuilding {
+        id: id.to_string(),
+        tile: [origin.x, origin.y],
+    });
+}
+
+/// Spawns every placed building that has no entity yet, both freshly placed and loaded
+/// from a save.
+fn spawn_placed_buildings(
+    mut commands: Commands,
+    placed: Res<PlacedBuildings>,
+    registry: Res<BuildingRegistry>,
+    asset_server: Res<AssetServer>,
+    home: Query<(Entity, &TileGrid), With<HomeMap>>,
+    mut spawned: Local<(Option<Entity>, usize)>,
+) {
+    let Ok((home, grid)) = home.single() else {
+        return;
+    };
+    // Placed buildings are despawned with the home map; spawn them again when it returns.
+    if spawned.0 != Some(home) {
+        *spawned = (Some(home), 0);
+    }
+
+    for building in placed.0.iter().skip(spawned.1) {
+        let building_definition = registry
+            .get(&building.id)
+            .and_then(|definition| Some((definition, definition.construction?)));
+        let Some((definition, construction)) = building_definition else {
+            warn!("Skipping placed building with unknown id: {}", building.id);
+            continue;
+        };
+
+        let origin = TilePosition {
+            x: building.tile[0],
+            y: building.tile[1],
+        };
+        let Some([footprint, entrance]) = placement_rects(origin, &construction) else {
+            continue;
+        };
+        let bounds = grid.rect_bounds(footprint);
+        let size = bounds.size();
+
+        commands.spawn_zone(
+            footprint,
+            (
+                Name::new(definition.display_name.clone()),
+                ChildOf(home),
+                Solid,
+                Sprite {
+                    custom_size: Some(size),
+                    ..Sprite::from_image(asset_server.load(&definition.icon))
+                },
+                Transform::from_translation(bounds.center().extend(0.0)),
+                YSort {
+                    foot_offset: -size.y / 2.0,
+                },
+            ),
+        );
+        commands
+            .spawn_entrance(BuildingId::new(building.id.clone()), entrance)
+            .insert(ChildOf(home));
+    }
+
+    spawned.1 = placed.0.len();
+}
@@ -0,0 +1,61 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
+
+/// Loads any deserializable asset from a JSON file with one of the given extensions.
+///
+/// Gameplay data (animations, buildings, dialogue, ...) is authored as plain JSON so that
+/// artists and designers can extend it without touching Rust code.
+pub struct JsonAssetLoader<A> {
+    extensions: &'static [&'static str],
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> AssetLoader for JsonAssetLoader<A>
+where
+    A: Asset + DeserializeOwned,
+{
+    type Asset = A;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<A, BevyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
+
+pub trait JsonAssetAppExt {
+    /// Registers `A` as an asset loaded from JSON files ending in one of `extensions`.
+    fn init_json_asset<A: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self;
+}
+
+impl JsonAssetAppExt for App {
+    fn init_json_asset<A: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self {
+        self.init_asset::<A>()
+            .register_asset_loader(JsonAssetLoader::<A> {
+                extensions,
+                _marker: PhantomData,
+            })
+    }
+}
@@ -0,0 +1,453 @@
+use bevy::prelude::*;
+
+use crate::{
+    collision::CollisionMap,
+    components::{
+        BuildingId, Dialogue, Doorway, Player, PlayerMovementSet, PreviousTilePosition, Shelter,
+        Solid, TileGroup, TilePosition, TileRect,
+    },
+    grid::TileGrid,
+    keepers::{Keeper, KeeperState},
+    map::MapGrids,
+    weather::SeeksShelter,
+};
+
+const TOGGLE_OVERLAY_KEY: KeyCode = KeyCode::F3;
+/// Grid lines are skipped when zoomed out this far, to keep the overlay cheap.
+const MAX_GRID_TILES: u32 = 4096;
+const GRID_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.15);
+const COLLISION_COLOR: Color = Color::srgba(1.0, 0.2, 0.2, 0.6);
+const PATH_COLOR: Color = Color::srgb(1.0, 0.9, 0.2);
+const CAMERA_COLOR: Color = Color::srgb(0.3, 0.9, 1.0);
+const OTHER_ZONE_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+/// Zone types in the order they are listed; a zone is drawn in the color of its first.
+const ZONE_KINDS: [(&str, Color); 5] = [
+    ("solid", Color::srgb(1.0, 0.3, 0.3)),
+    ("entrance", Color::srgb(0.3, 1.0, 0.4)),
+    ("doorway", Color::srgb(0.4, 0.6, 1.0)),
+    ("dialogue", Color::srgb(1.0, 0.8, 0.3)),
+    ("shelter", Color::srgb(0.4, 1.0, 1.0)),
+];
+
+/// Developer overlay toggled with F3, only built into debug builds.
+///
+/// Draws the tile grid, zones colored by type, collision tiles, NPC paths and the camera
+/// bounds with gizmos, and shows a panel listing the zones under the cursor. Right-click
+/// picks the panel's teleport target, which the X and Y buttons nudge.
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlay>()
+            .add_systems(Startup, spawn_debug_panel)
+            .add_systems(
+                Update,
+                (
+                    toggle_overlay,
+                    press_debug_buttons,
+                    (
+                        track_cursor,
+                        draw_grid,
+                        draw_zones,
+                        draw_collision,
+                        draw_paths,
+                        draw_camera_bounds,
+                        update_debug_panel,
+                    )
+                        .chain()
+                        .run_if(|overlay: Res<DebugOverlay>| overlay.visible),
+                )
+                    .chain()
+                    .after(PlayerMovementSet),
+            )
+            .add_observer(teleport_player);
+    }
+}
+
+/// Moves the player straight to `to`, e.g. from the debug panel.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TeleportPlayer {
+    pub to: TilePosition,
+}
+
+#[derive(Resource)]
+struct DebugOverlay {
+    visible: bool,
+    /// The tile under the cursor on the active map.
+    cursor: Option<TilePosition>,
+    /// Where the teleport button sends the player.
+    target: TilePosition,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            cursor: None,
+            target: TilePosition { x: 0, y: 0 },
+        }
+    }
+}
+
+#[derive(Component)]
+struct DebugPanel;
+
+#[derive(Component)]
+struct DebugPanelText;
+
+#[derive(Component, Clone, Copy)]
+enum DebugButton {
+    Left,
+    Right,
+    Down,
+    Up,
+    Teleport,
+}
+
+impl DebugButton {
+    fn label(self) -> &'static str {
+        match self {
+            DebugButton::Left => "X-",
+            DebugButton::Right => "X+",
+            DebugButton::Down => "Y-",
+            DebugButton::Up => "Y+",
+            DebugButton::Teleport => "Teleport",
+        }
+    }
+}
+
+/// The types of a zone, by which of the [`ZONE_KINDS`] components it has.
+fn zone_kinds(flags: [bool; 5]) -> impl Iterator<Item = (&'static str, Color)> {
+    ZONE_KINDS
+        .into_iter()
+        .zip(flags)
+        .filter_map(|(kind, has)| has.then_some(kind))
+}
+
+type ZoneQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static TileGroup,
+        Option<&'static Name>,
+        Has<Solid>,
+        Has<BuildingId>,
+        Has<Doorway>,
+        Has<Dialogue>,
+        Has<Shelter>,
+    ),
+>;
+
+fn group_rect(group: &TileGroup) -> TileRect {
+    match group {
+        TileGroup::Rectangle(rect) => TileRect::new(
+            rect.bottom_left,
+            rect.top_right.x - rect.bottom_left.x + 1,
+            rect.top_right.y - rect.bottom_left.y + 1,
+        ),
+    }
+}
+
+fn tile_bounds(grid: &TileGrid, tile: TilePosition) -> Rect {
+    grid.rect_bounds(TileRect::new(tile, 1, 1))
+}
+
+fn spawn_debug_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            DebugPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                // Bottom right, clear of the top panels, the toasts and the prompt. The
+                // dialogue box covers it while open.
+                bottom: px(12),
+                right: px(12),
+                flex_direction: FlexDirection::Column,
+                row_gap: px(8),
+                padding: UiRect::all(px(12)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+        ))
+        .with_children(|panel| {
+            panel.spawn((DebugPanelText, Text::new("")));
+            panel
+                .spawn(Node {
+                    column_gap: px(8),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for button in [
+                        DebugButton::Left,
+                        DebugButton::Right,
+                        DebugButton::Down,
+                        DebugButton::Up,
+                        DebugButton::Teleport,
+                    ] {
+                        row.spawn((
+                            button,
+                            Button,
+                            Node {
+                                padding: UiRect::all(px(8)),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.1)),
+                        ))
+                        .with_child(Text::new(button.label()));
+                    }
+                });
+        });
+}
+
+fn toggle_overlay(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut panel: Single<&mut Node, With<DebugPanel>>,
+) {
+    if !kb_input.just_pressed(TOGGLE_OVERLAY_KEY) {
+        return;
+    }
+    overlay.visible = !overlay.visible;
+    panel.display = if overlay.visible {
+        Display::Flex
+    } else {
+        Display::None
+    };
+}
+
+fn press_debug_buttons(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &DebugButton), Changed<Interaction>>,
+    mut overlay: ResMut<DebugOverlay>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let target = &mut overlay.target;
+        match button {
+            DebugButton::Left => target.x = target.x.saturating_sub(1),
+            DebugButton::Right => target.x += 1,
+            DebugButton::Down => target.y = target.y.saturating_sub(1),
+            DebugButton::Up => target.y += 1,
+            DebugButton::Teleport => {
+                commands.trigger(TeleportPlayer { to: *target });
+            }
+        }
+    }
+}
+
+/// Finds the tile under the cursor. Right-click makes it the teleport target.
+fn track_cursor(
+    mut overlay: ResMut<DebugOverlay>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    grids: MapGrids,
+) {
+    let (camera, camera_transform) = *camera;
+    overlay.cursor = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+        .zip(grids.active())
+        .and_then(|(world, grid)| grid.world_to_tile(world));
+
+    if let Some(cursor) = overlay.cursor
+        && mouse_input.just_pressed(MouseButton::Right)
+    {
+        overlay.target = cursor;
+    }
+}
+
+/// Outlines the tiles in view on the active map.
+fn draw_grid(
+    mut gizmos: Gizmos,
+    camera: Single<(&GlobalTransform, &Projection), With<Camera2d>>,
+    grids: MapGrids,
+) {
+    let (transform, projection) = *camera;
+    let (Some(grid), Projection::Orthographic(ortho)) = (grids.active(), projection) else {
+        return;
+    };
+    let area = ortho.area;
+    let center = transform.translation().truncate();
+    let corners = [
+        area.min,
+        area.max,
+        Vec2::new(area.min.x, area.max.y),
+        Vec2::new(area.max.x, area.min.y),
+    ]
+    .map(|corner| {
+        grid.world_to_tile(center + corner)
+            .unwrap_or(TilePosition { x: 0, y: 0 })
+    });
+    let min_x = corners.iter().map(|tile| tile.x).min().unwrap_or(0);
+    let max_x = corners.iter().map(|tile| tile.x).max().unwrap_or(0) + 1;
+    let min_y = corners.iter().map(|tile| tile.y).min().unwrap_or(0);
+    let max_y = corners.iter().map(|tile| tile.y).max().unwrap_or(0) + 1;
+    if (max_x - min_x + 1) * (max_y - min_y + 1) > MAX_GRID_TILES {
+        return;
+    }
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let bounds = tile_bounds(grid, TilePosition { x, y });
+            gizmos.rect_2d(bounds.center(), bounds.size(), GRID_COLOR);
+        }
+    }
+}
+
+/// Outlines the area the camera sees, in world space like [`draw_grid`].
+fn draw_camera_bounds(
+    mut gizmos: Gizmos,
+    camera: Single<(&GlobalTransform, &Projection), With<Camera2d>>,
+) {
+    let (transform, projection) = *camera;
+    let Projection::Orthographic(ortho) = projection else {
+        return;
+    };
+    let center = transform.translation().truncate() + ortho.area.center();
+    // Inset so the outline stays on screen.
+    gizmos.rect_2d(center, ortho.area.size() - Vec2::splat(4.0), CAMERA_COLOR);
+}
+
+fn draw_zones(mut gizmos: Gizmos, grids: MapGrids, zones: ZoneQuery) {
+    for (entity, group, _, solid, building, doorway, dialogue, shelter) in zones.iter() {
+        let Some(grid) = grids.of(entity).or(grids.active()) else {
+            continue;
+        };
+        let color = zone_kinds([solid, building, doorway, dialogue, shelter])
+            .next()
+            .map_or(OTHER_ZONE_COLOR, |(_, color)| color);
+        let bounds = grid.rect_bounds(group_rect(group));
+        gizmos.rect_2d(bounds.center(), bounds.size(), color);
+    }
+}
+
+fn draw_collision(mut gizmos: Gizmos, grids: MapGrids, collision_map: Res<CollisionMap>) {
+    let Some(grid) = grids.active() else {
+        return;
+    };
+    for tile in collision_map.tiles() {
+        let bounds = tile_bounds(grid, tile);
+        gizmos.rect_2d(bounds.center(), bounds.size() * 0.6, COLLISION_COLOR);
+    }
+}
+
+/// Draws the path each keeper or shelter seeker is walking.
+fn draw_paths(
+    mut gizmos: Gizmos,
+    grids: MapGrids,
+    keepers: Query<(&TilePosition, &Keeper)>,
+    seekers: Query<(&TilePosition, &SeeksShelter)>,
+) {
+    let Some(grid) = grids.active() else {
+        return;
+    };
+    let keeper_paths = keepers
+        .iter()
+        .filter_map(|(position, keeper)| match &keeper.state {
+            KeeperState::Walking { path } => Some((position, path)),
+            _ => None,
+        });
+    let seeker_paths = seekers
+        .iter()
+        .map(|(position, seeker)| (position, seeker.path()));
+
+    for (position, path) in keeper_paths.chain(seeker_paths) {
+        if path.is_empty() {
+            continue;
+        }
+        let points = std::iter::once(position)
+            .chain(path)
+            .map(|tile| grid.tile_center(*tile));
+        gizmos.linestrip_2d(points, PATH_COLOR);
+    }
+}
+
+fn update_debug_panel(
+    overlay: Res<DebugOverlay>,
+    zones: ZoneQuery,
+    mut text: Single<&mut Text, With<DebugPanelText>>,
+) {
+    let mut lines = Vec::new();
+    match overlay.cursor {
+        Some(cursor) => {
+            lines.push(format!("Cursor: ({}, {})", cursor.x, cursor.y));
+            for (entity, group, name, solid, building, doorway, dialogue, shelter) in zones.iter() {
+                if !group.contains(cursor) {
+                    continue;
+                }
+                let kinds: Vec<&str> = zone_kinds([solid, building, doorway, dialogue, shelter])
+                    .map(|(kind, _)| kind)
+                    .collect();
+                let name = name.map_or_else(|| format!("{:?}", entity), |name| name.to_string());
+                lines.push(format!("  {} [{}]", name, kinds.join(", ")));
+            }
+        }
+        None => lines.push("Cursor: off the map".to_string()),
+    }
+    lines.push(format!(
+        "Teleport to: ({}, {})",
+        overlay.target.x, overlay.target.y
+    ));
+
+    let content = lines.join("\n");
+    if text.0 != content {
+        text.0 = content;
+    }
+}
+
+fn teleport_player(
+    trigger: On<TeleportPlayer>,
+    mut player: Query<(&mut TilePosition, &mut PreviousTilePosition), With<Player>>,
+) {
+    let Ok((mut position, mut previous)) = player.single_mut() else {
+        return;
+    };
+    let to = trigger.event().to;
+    info!("Teleporting the player to {:?}", to);
+    *position = to;
+    // Jump straight there instead of sliding across the map.
+    *previous = PreviousTilePosition(None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::ActiveMap, testing::TestApp};
+
+    const TEST_MAP: &str = "test/entrance_test.tmx";
+
+    #[test]
+    fn zones_list_every_type_they_have() {
+        let kinds: Vec<&str> = zone_kinds([true, false, true, false, false])
+            .map(|(kind, _)| kind)
+            .collect();
+        assert_eq!(kinds, ["solid", "doorway"]);
+        assert_eq!(zone_kinds([false; 5]).count(), 0);
+    }
+
+    #[test]
+    fn teleport_puts_the_player_on_the_tile() {
+        let mut app = TestApp::with_plugins(TEST_MAP, DebugPlugin);
+        app.wait_for_zones(1);
+
+        let to = TilePosition { x: 4, y: 3 };
+        app.world().trigger(TeleportPlayer { to });
+        app.step(2);
+
+        assert_eq!(app.player_tile(), to);
+        let world = app.world();
+        let center = world
+            .query_filtered::<&TileGrid, With<ActiveMap>>()
+            .single(world)
+            .unwrap()
+            .tile_center(to);
+        let player = app.player();
+        let transform = app.world().get::<Transform>(player).unwrap();
+        assert_eq!(transform.translation.truncate(), center);
+    }
+}
@@ -0,0 +1,609 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_tiled::prelude::TiledObject;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::Counters,
+    clock::{GameClock, deserialize_time_of_day},
+    components::{
+        BuildingEntrance, BuildingId, Dialogue, Player, PlayerMovementSet, TileGroup, TilePosition,
+        TileRect,
+    },
+    data::JsonAssetAppExt,
+    economy::{Cost, Economy},
+    entrance::zone_tile_group,
+    interaction::Interacted,
+    map::MapGrids,
+};
+
+/// Characters the typewriter reveals per second.
+const CHARACTERS_PER_SECOND: f32 = 40.0;
+const ADVANCE_KEY: KeyCode = KeyCode::KeyE;
+const ADVANCE_BUTTON: GamepadButton = GamepadButton::South;
+const CHOICE_KEYS: [KeyCode; 4] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+];
+const PORTRAIT_SIZE: f32 = 160.0;
+
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Dialogue>()
+            .init_json_asset::<DialogueScript>(&["dialogue.json"])
+            .init_resource::<ActiveDialogue>()
+            .init_resource::<DialogueFlags>()
+            .add_systems(Startup, spawn_dialogue_box)
+            .add_systems(
+                Update,
+                (
+                    (
+                        snap_dialogue_zones,
+                        load_dialogue_scripts,
+                        reload_open_dialogue,
+                    ),
+                    start_dialogue_on_enter.after(PlayerMovementSet),
+                    advance_dialogue,
+                    refresh_dialogue_box,
+                    type_dialogue_text,
+                )
+                    .chain(),
+            )
+            .add_observer(start_dialogue_on_interact);
+    }
+}
+
+/// A branching conversation, loaded from a `*.dialogue.json` file.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct DialogueScript {
+    /// Candidate first nodes; the first whose condition holds opens the conversation.
+    pub start: Vec<Branch>,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Branch {
+    pub node: String,
+    #[serde(default)]
+    pub condition: Option<DialogueCondition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueNode {
+    pub speaker: String,
+    /// Image shown next to the text, relative to the assets folder.
+    #[serde(default)]
+    pub portrait: Option<String>,
+    pub text: String,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+    /// Node shown after this one when it has no choices; the conversation ends without one.
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueChoice {
+    pub text: String,
+    /// Hidden unless the condition holds when the node is shown.
+    #[serde(default)]
+    pub condition: Option<DialogueCondition>,
+    /// Flags set when the choice is picked.
+    #[serde(default)]
+    pub set_flags: Vec<String>,
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogueCondition {
+    /// The sanctuary holds at least this many resources.
+    Has(Cost),
+    /// The time of day is in `[from, until)`, given as `"HH:MM"` and wrapping past midnight.
+    TimeBetween {
+        #[serde(deserialize_with = "deserialize_time_of_day")]
+        from: u32,
+        #[serde(deserialize_with = "deserialize_time_of_day")]
+        until: u32,
+    },
+    /// A flag set by an earlier choice.
+    Flag(String),
+    /// The named [`Counters`] entry reached `at_least`.
+    Counter {
+        name: String,
+        at_least: u64,
+    },
+    Not(Box<DialogueCondition>),
+    All(Vec<DialogueCondition>),
+    Any(Vec<DialogueCondition>),
+}
+
+/// Flags set by dialogue choices. Saved with the game.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DialogueFlags(HashSet<String>);
+
+/// The game state dialogue conditions are evaluated against.
+#[derive(SystemParam)]
+struct DialogueContext<'w> {
+    clock: Res<'w, GameClock>,
+    economy: Res<'w, Economy>,
+    counters: Res<'w, Counters>,
+    flags: ResMut<'w, DialogueFlags>,
+}
+
+impl DialogueContext<'_> {
+    fn is_met(&self, condition: &DialogueCondition) -> bool {
+        match condition {
+            DialogueCondition::Has(cost) => self.economy.can_afford(*cost),
+            DialogueCondition::TimeBetween { from, until } => {
+                let now = self.clock.minute_of_day();
+                if from <= until {
+                    (*from..*until).contains(&now)
+                } else {
+                    now >= *from || now < *until
+                }
+            }
+            DialogueCondition::Flag(flag) => self.flags.0.contains(flag),
+            DialogueCondition::Counter { name, at_least } => self.counters.get(name) >= *at_least,
+            DialogueCondition::Not(condition) => !self.is_met(condition),
+            DialogueCondition::All(conditions) => conditions.iter().all(|c| self.is_met(c)),
+            DialogueCondition::Any(conditions) => conditions.iter().any(|c| self.is_met(c)),
+        }
+    }
+
+    fn allows(&self, condition: &Option<DialogueCondition>) -> bool {
+        condition
+            .as_ref()
+            .is_none_or(|condition| self.is_met(condition))
+    }
+}
+
+/// The conversation on screen, if any. Player movement and interaction pause while it is
+/// open.
+#[derive(Resource, Default)]
+pub struct ActiveDialogue(Option<DialogueSession>);
+
+impl ActiveDialogue {
+    pub fn is_open(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+struct DialogueSession {
+    script: Handle<DialogueScript>,
+    node: String,
+    /// Indices of the node's choices whose conditions held when it was shown.
+    choices: Vec<usize>,
+    /// Input is ignored on the frame the conversation opens, so the key that started it
+    /// does not also skip the first line.
+    ready: bool,
+}
+
+impl DialogueSession {
+    fn enter(
+        script_handle: &Handle<DialogueScript>,
+        script: &DialogueScript,
+        node: &str,
+        context: &DialogueContext,
+    ) -> Option<Self> {
+        let Some(definition) = script.nodes.get(node) else {
+            warn!(
+                "Dialogue node {:?} does not exist, ending conversation",
+                node
+            );
+            return None;
+        };
+        let choices = definition
+            .choices
+            .iter()
+            .enumerate()
+            .filter(|(_, choice)| context.allows(&choice.condition))
+            .map(|(index, _)| index)
+            .collect();
+
+        Some(Self {
+            script: script_handle.clone(),
+            node: node.to_string(),
+            choices,
+            ready: false,
+        })
+    }
+
+    fn start(
+        script_handle: &Handle<DialogueScript>,
+        scripts: &Assets<DialogueScript>,
+        context: &DialogueContext,
+    ) -> Option<Self> {
+        let Some(script) = scripts.get(script_handle) else {
+            warn!("Dialogue script is not loaded yet");
+            return None;
+        };
+        let branch = script
+            .start
+            .iter()
+            .find(|branch| context.allows(&branch.condition))?;
+        Self::enter(script_handle, script, &branch.node, context)
+    }
+}
+
+/// Loaded script of a [`Dialogue`] zone or NPC.
+#[derive(Component)]
+struct DialogueScriptHandle(Handle<DialogueScript>);
+
+#[derive(Component)]
+struct DialogueBox;
+
+#[derive(Component)]
+struct DialoguePortrait;
+
+#[derive(Component)]
+struct DialogueSpeaker;
+
+/// Text revealed a few characters at a time.
+#[derive(Component, Default)]
+struct DialogueText {
+    full: String,
+    revealed: f32,
+}
+
+impl DialogueText {
+    fn is_done(&self) -> bool {
+        self.revealed as usize >= self.full.chars().count()
+    }
+}
+
+#[derive(Component)]
+struct DialogueChoices;
+
+/// Index into the current node's choices.
+#[derive(Component)]
+struct DialogueChoiceButton(usize);
+
+fn load_dialogue_scripts(
+    mut commands: Commands,
+    dialogues: Query<(Entity, &Dialogue), Without<DialogueScriptHandle>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, dialogue) in dialogues.iter() {
+        commands
+            .entity(entity)
+            .insert(DialogueScriptHandle(asset_server.load(&dialogue.script)));
+    }
+}
+
+/// Shows the open node again when its script changes on disk. Ends the conversation if
+/// the node no longer exists.
+fn reload_open_dialogue(
+    mut asset_events: MessageReader<AssetEvent<DialogueScript>>,
+    mut active: ResMut<ActiveDialogue>,
+    scripts: Res<Assets<DialogueScript>>,
+    context: DialogueContext,
+) {
+    for event in asset_events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        let Some(session) = active
+            .0
+            .as_ref()
+            .filter(|session| session.script.id() == *id)
+        else {
+            continue;
+        };
+
+        info!(
+            "Dialogue script changed on disk, reloading {:?}",
+            session.node
+        );
+        let reloaded = scripts.get(*id).and_then(|script| {
+            DialogueSession::enter(&session.script, script, &session.node, &context)
+        });
+        active.0 = reloaded;
+    }
+}
+
+/// Gives Tiled signs and other dialogue zones a [`TileGroup`]. Entrances are snapped (and
+/// validated) by the entrance plugin instead.
+fn snap_dialogue_zones(
+    mut commands: Commands,
+    grids: MapGrids,
+    query: Query<
+        (
+            Entity,
+            Option<&Transform>,
+            Option<&TiledObject>,
+            Option<&TileRect>,
+        ),
+        (
+            With<Dialogue>,
+            Or<(With<TiledObject>, With<TileRect>)>,
+            Without<TileGroup>,
+            Without<BuildingEntrance>,
+            Without<BuildingId>,
+        ),
+    >,
+) {
+    for (entity, transform, tiled_object, rect) in query.iter() {
+        let label = format!("Dialogue zone {:?}", entity);
+        let grid = grids.of(entity);
+        if let Some(tile_group) = zone_tile_group(&label, grid, transform, tiled_object, rect) {
+            commands.entity(entity).insert(tile_group);
+        }
+    }
+}
+
+fn start_dialogue_on_interact(
+    trigger: On<Interacted>,
+    scripts_on: Query<&DialogueScriptHandle>,
+    scripts: Res<Assets<DialogueScript>>,
+    context: DialogueContext,
+    mut active: ResMut<ActiveDialogue>,
+) {
+    let Ok(handle) = scripts_on.get(trigger.event().target) else {
+        return;
+    };
+    if active.is_open() {
+        return;
+    }
+
+    active.0 = DialogueSession::start(&handle.0, &scripts, &context);
+}
+
+/// Starts `on_enter` dialogue when the player steps into its zone.
+fn start_dialogue_on_enter(
+    player: Single<Ref<TilePosition>, With<Player>>,
+    zones: Query<(&Dialogue, &TileGroup, &DialogueScriptHandle)>,
+    scripts: Res<Assets<DialogueScript>>,
+    context: DialogueContext,
+    mut active: ResMut<ActiveDialogue>,
+    mut last_position: Local<Option<TilePosition>>,
+) {
+    if !player.is_changed() {
+        return;
+    }
+    let position = **player;
+    let previous = last_position.replace(position);
+    if active.is_open() {
+        return;
+    }
+
+    let entered = zones.iter().find(|(dialogue, group, _)| {
+        dialogue.on_enter
+            && group.contains(position)
+            && previous.is_none_or(|previous| !group.contains(previous))
+    });
+    if let Some((_, _, handle)) = entered {
+        active.0 = DialogueSession::start(&handle.0, &scripts, &context);
+    }
+}
+
+/// Finishes the typewriter, picks a choice or moves on to the next node.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn advance_dialogue(
+    mut active: ResMut<ActiveDialogue>,
+    scripts: Res<Assets<DialogueScript>>,
+    mut context: DialogueContext,
+    mut text: Single<&mut DialogueText>,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    dialogue_box: Query<&Interaction, (Changed<Interaction>, With<DialogueBox>)>,
+    choice_buttons: Query<(&Interaction, &DialogueChoiceButton), Changed<Interaction>>,
+) {
+    // Only node changes should refresh the dialogue box.
+    let Some(session) = active.bypass_change_detection().0.as_mut() else {
+        return;
+    };
+    let Some(script) = scripts.get(&session.script) else {
+        active.0 = None;
+        return;
+    };
+    // The script may have changed on disk before the session caught up with it.
+    let Some(node) = script.nodes.get(&session.node) else {
+        warn!("Dialogue node {} no longer exists", session.node);
+        active.0 = None;
+        return;
+    };
+    if !session.ready {
+        session.ready = true;
+        return;
+    }
+
+    let pressed = kb_input.just_pressed(ADVANCE_KEY)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(ADVANCE_BUTTON))
+        || dialogue_box
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed);
+
+    if !text.is_done() {
+        if pressed {
+            text.revealed = text.full.chars().count() as f32;
+        }
+        return;
+    }
+
+    // Nodes whose choices are all hidden fall back to `next`.
+    let next = if session.choices.is_empty() {
+        if !pressed {
+            return;
+        }
+        node.next.clone()
+    } else {
+        let picked = CHOICE_KEYS
+            .iter()
+            .position(|key| kb_input.just_pressed(*key))
+            .or_else(|| {
+                choice_buttons
+                    .iter()
+                    .find(|(interaction, _)| **interaction == Interaction::Pressed)
+                    .map(|(_, button)| button.0)
+            })
+            .and_then(|slot| session.choices.get(slot).copied());
+        let Some(index) = picked else {
+            return;
+        };
+
+        let Some(choice) = node.choices.get(index) else {
+            warn!(
+                "Dialogue choice {} of {} no longer exists",
+                index, session.node
+            );
+            active.0 = None;
+            return;
+        };
+        for flag in choice.set_flags.iter() {
+            info!("Dialogue flag set: {}", flag);
+            context.flags.0.insert(flag.clone());
+        }
+        choice.next.clone()
+    };
+
+    let handle = session.script.clone();
+    active.0 = next.and_then(|next| DialogueSession::enter(&handle, script, &next, &context));
+}
+
+fn spawn_dialogue_box(mut commands: Commands) {
+    commands
+        .spawn((
+            DialogueBox,
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: px(12),
+                left: px(12),
+                right: px(12),
+                min_height: px(240),
+                column_gap: px(16),
+                padding: UiRect::all(px(16)),
+                display: Display::None,
+                ..default()
+            },
+            // Above the interaction prompt it covers.
+            GlobalZIndex(1),
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                DialoguePortrait,
+                ImageNode::default(),
+                Node {
+                    width: px(PORTRAIT_SIZE),
+                    height: px(PORTRAIT_SIZE),
+                    flex_shrink: 0.0,
+                    ..default()
+                },
+            ));
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    flex_grow: 1.0,
+                    row_gap: px(8),
+                    ..default()
+                })
+                .with_children(|column| {
+                    column.spawn((
+                        DialogueSpeaker,
+                        Text::new(""),
+                        TextColor(Color::srgb(1.0, 0.85, 0.4)),
+                    ));
+                    column.spawn((DialogueText::default(), Text::new("")));
+                    column.spawn((
+                        DialogueChoices,
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: px(8),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+/// Shows the current node when the conversation opens, advances or closes.
+#[allow(clippy::too_many_arguments)]
+fn refresh_dialogue_box(
+    mut commands: Commands,
+    active: Res<ActiveDialogue>,
+    scripts: Res<Assets<DialogueScript>>,
+    asset_server: Res<AssetServer>,
+    mut dialogue_box: Single<&mut Node, With<DialogueBox>>,
+    portrait: Single<(&mut ImageNode, &mut Node), (With<DialoguePortrait>, Without<DialogueBox>)>,
+    mut speaker: Single<&mut Text, (With<DialogueSpeaker>, Without<DialogueText>)>,
+    mut text: Single<&mut DialogueText>,
+    choices: Single<Entity, With<DialogueChoices>>,
+) {
+    if !active.is_changed() {
+        return;
+    }
+
+    let current = active.0.as_ref().and_then(|session| {
+        scripts
+            .get(&session.script)
+            .and_then(|script| script.nodes.get(&session.node))
+            .map(|node| (session, node))
+    });
+    let Some((session, node)) = current else {
+        dialogue_box.display = Display::None;
+        return;
+    };
+    dialogue_box.display = Display::Flex;
+
+    let (mut image, mut portrait_node) = portrait.into_inner();
+    match &node.portrait {
+        Some(path) => {
+            image.image = asset_server.load(path);
+            portrait_node.display = Display::Flex;
+        }
+        None => portrait_node.display = Display::None,
+    }
+
+    speaker.0 = node.speaker.clone();
+    **text = DialogueText {
+        full: node.text.clone(),
+        revealed: 0.0,
+    };
+
+    commands.entity(*choices).despawn_children();
+    for (slot, index) in session.choices.iter().enumerate() {
+        let Some(choice) = node.choices.get(*index) else {
+            continue;
+        };
+        commands.entity(*choices).with_children(|parent| {
+            parent
+                .spawn((
+                    DialogueChoiceButton(slot),
+                    Button,
+                    Node {
+                        padding: UiRect::all(px(8)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.1)),
+                ))
+                .with_child(Text::new(format!("{}. {}", slot + 1, choice.text)));
+        });
+    }
+}
+
+fn type_dialogue_text(time: Res<Time>, text: Single<(&mut DialogueText, &mut Text)>) {
+    let (mut typewriter, mut text) = text.into_inner();
+    if !typewriter.is_done() {
+        typewriter.revealed += CHARACTERS_PER_SECOND * time.delta_secs();
+    }
+
+    let shown: String = typewriter
+        .full
+        .chars()
+        .take(typewriter.revealed as usize)
+        .collect();
+    if text.0 != shown {
+        text.0 = shown;
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buildings::BuildingRegistry, clock::GameClock, components::BuildingEntrance,
+    weather::WeatherState,
+};
+
+/// Coins donated per in-game hour.
+const DONATIONS_PER_HOUR: f64 = 5.0;
+const STARTING_COINS: f64 = 50.0;
+const STARTING_FOOD_CAPACITY: f64 = 100.0;
+/// Food the Nutrition House prepares per in-game hour while it is open.
+const STARTING_PREP_PER_HOUR: f64 = 10.0;
+
+pub struct EconomyPlugin;
+
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Economy>().add_systems(
+            FixedUpdate,
+            run_economy.run_if(resource_exists::<BuildingRegistry>),
+        );
+    }
+}
+
+/// A price in economy resources.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Cost {
+    pub coins: u32,
+    pub food: u32,
+}
+
+impl std::fmt::Display for Cost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.coins, self.food) {
+            (0, 0) => write!(f, "free"),
+            (coins, 0) => write!(f, "{} coins", coins),
+            (0, food) => write!(f, "{} food", food),
+            (coins, food) => write!(f, "{} coins, {} food", coins, food),
+        }
+    }
+}
+
+/// The sanctuary's resources. Saved with the game.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct Economy {
+    coins: f64,
+    food: f64,
+    pub food_capacity: f64,
+    pub prep_per_hour: f64,
+}
+
+impl Default for Economy {
+    fn default() -> Self {
+        Self {
+            coins: STARTING_COINS,
+            food: 0.0,
+            food_capacity: STARTING_FOOD_CAPACITY,
+            prep_per_hour: STARTING_PREP_PER_HOUR,
+        }
+    }
+}
+
+impl Economy {
+    pub fn coins(&self) -> u32 {
+        self.coins as u32
+    }
+
+    pub fn food(&self) -> u32 {
+        self.food as u32
+    }
+
+    pub fn can_afford(&self, cost: Cost) -> bool {
+        self.coins() >= cost.coins && self.food() >= cost.food
+    }
+
+    /// Pays `cost` if affordable, returning whether it was paid.
+    pub fn try_spend(&mut self, cost: Cost) -> bool {
+        if !self.can_afford(cost) {
+            return false;
+        }
+        self.coins -= cost.coins as f64;
+        self.food -= cost.food as f64;
+        true
+    }
+
+    /// Pays out `amount`, e.g. a quest reward.
+    pub fn receive(&mut self, amount: Cost) {
+        self.coins += amount.coins as f64;
+        self.add_food(amount.food as f64);
+    }
+
+    /// Adds food, capped at the storage capacity.
+    pub fn add_food(&mut self, amount: f64) {
+        self.food = (self.food + amount).min(self.food_capacity);
+    }
+}
+
+/// Accrues donations, fewer in the heat, and Nutrition House food prep for the in-game
+/// time that passed.
+fn run_economy(
+    clock: Res<GameClock>,
+    registry: Res<BuildingRegistry>,
+    weather: Res<WeatherState>,
+    mut economy: ResMut<Economy>,
+    mut last_minutes: Local<Option<f64>>,
+) {
+    let now = clock.total_minutes();
+    let Some(last) = last_minutes.replace(now) else {
+        return;
+    };
+    let hours = (now - last) / 60.0;
+    if hours <= 0.0 {
+        return;
+    }
+
+    economy.coins += DONATIONS_PER_HOUR * weather.current.visitor_factor() * hours;
+
+    let nutrition_house = BuildingEntrance::NutritionHouse
+        .building_id()
+        .and_then(|building| registry.get(&building.id));
+    if nutrition_house.is_some_and(|definition| definition.opening_hours.is_open(clock.hour())) {
+        let prepared = economy.prep_per_hour * hours;
+        economy.add_food(prepared);
+    }
+}
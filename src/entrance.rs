@@ -1,198 +1,432 @@
 use bevy::prelude::*;
 use bevy_ecs_tiled::prelude::*;
 
-use crate::components::{
-    BuildingEntrance, Player, PlayerMovementSet, PlayerSetupSet, RectangleTileGroup, TileGroup,
-    TilePosition,
+use crate::{
+    buildings::BuildingRegistry,
+    components::{
+        BuildingEntrance, BuildingId, InEntrance, Interactable, PlayerMovementSet,
+        RectangleTileGroup, TileGroup, TilePosition, TileRect,
+    },
+    grid::TileGrid,
+    map::MapGrids,
 };
 
 pub struct EntrancePlugin;
 
 impl Plugin for EntrancePlugin {
     fn build(&self, app: &mut App) {
-        // let player = app
-        //     .world_mut()
-        //     .query_filtered::<Entity, With<Player>>()
-        //     .single(app.world())
-        //     .expect("TODO: how to handle this error?");
-
-        app.add_systems(Update, validate_and_snap_entrances)
-            .add_systems(
-                Update,
-                (
-                    check_player_enter_building,
-                    check_player_exit_building,
-                    handle_player_entering_building,
-                )
-                    .chain()
-                    .after(PlayerMovementSet),
-            )
-            .add_systems(
-                Startup,
-                (|player: Single<Entity, With<Player>>, mut commands: Commands| {
-                    commands.entity(*player);
-                })
-                .after(PlayerSetupSet),
-            )
-            .add_observer(player_exiting_building_observer);
+        app.add_systems(
+            Update,
+            (resnap_entrances, validate_and_snap_entrances)
+                .chain()
+                .run_if(resource_exists::<BuildingRegistry>),
+        )
+        .add_systems(FixedUpdate, check_actor_entrances.after(PlayerMovementSet));
     }
 }
 
-#[derive(Debug, Event)]
-pub struct PlayerEnteredBuildingEvent {
-    pub entrance: BuildingEntrance,
-    // #[event_target]
-    // pub player: Entity,
+/// Triggered on an actor (player or NPC) when it steps into a building entrance.
+#[derive(Debug, EntityEvent)]
+pub struct EnteredBuildingEvent {
+    #[event_target]
+    pub actor: Entity,
+    pub entrance: Entity,
+    pub building: BuildingId,
 }
 
-#[derive(Debug, Event)]
-pub struct PlayerExitedBuildingEvent {
-    pub entrance: BuildingEntrance,
-    // #[event_target]
-    // pub player: Entity,
+/// Triggered on an actor when it steps out of a building entrance.
+#[derive(Debug, EntityEvent)]
+pub struct ExitedBuildingEvent {
+    #[event_target]
+    pub actor: Entity,
+    pub entrance: Entity,
+    pub building: BuildingId,
 }
 
-/// Check if the player is within a building entrance and attach the marker component.
-fn check_player_enter_building(
-    player: Single<
-        (&TilePosition, Entity),
-        (
-            With<Player>,
-            Changed<TilePosition>,
-            Without<BuildingEntrance>,
-        ),
-    >,
-    entrance: Single<(&TileGroup, &BuildingEntrance)>,
-    mut commands: Commands,
-) {
-    let (player_pos, player_entity) = *player;
-
-    let (entrance_pos, entrance) = *entrance;
-    match entrance_pos {
-        TileGroup::Rectangle(rect) => {
-            if player_pos.x >= rect.bottom_left.x
-                && player_pos.x <= rect.top_right.x
-                && player_pos.y >= rect.bottom_left.y
-                && player_pos.y <= rect.top_right.y
-            {
-                commands.entity(player_entity).insert(*entrance);
-            }
-        }
-    }
+/// Triggered on an entrance when an actor steps into it.
+///
+/// Mirrors [`EnteredBuildingEvent`] so observers can be scoped to a single door.
+#[derive(Debug, EntityEvent)]
+pub struct EntranceEnteredEvent {
+    #[event_target]
+    pub entrance: Entity,
+    pub actor: Entity,
+    pub building: BuildingId,
 }
 
-fn check_player_exit_building(
-    player: Single<
-        (&TilePosition, Entity),
-        (With<Player>, Changed<TilePosition>, With<BuildingEntrance>),
-    >,
-    entrances: Query<(&TileGroup, &BuildingEntrance)>,
+/// Triggered on an entrance when an actor steps out of it.
+#[derive(Debug, EntityEvent)]
+pub struct EntranceExitedEvent {
+    #[event_target]
+    pub entrance: Entity,
+    pub actor: Entity,
+    pub building: BuildingId,
+}
+
+/// Tracks which entrance each moving actor stands in and fires enter/exit events.
+///
+/// Global observers should listen to the actor-targeted events only; the entrance-targeted
+/// ones exist for door-scoped observers.
+fn check_actor_entrances(
+    actors: Query<(Entity, &TilePosition, Option<&InEntrance>), Changed<TilePosition>>,
+    entrances: Query<(Entity, &TileGroup, &BuildingId)>,
     mut commands: Commands,
 ) {
-    let (player_pos, player_entity) = *player;
-
-    for (entrance_pos, entrance) in entrances.iter() {
-        match entrance_pos {
-            TileGroup::Rectangle(rect) => {
-                let inside = player_pos.x >= rect.bottom_left.x
-                    && player_pos.x <= rect.top_right.x
-                    && player_pos.y >= rect.bottom_left.y
-                    && player_pos.y <= rect.top_right.y;
-
-                if !inside {
-                    commands.trigger(PlayerExitedBuildingEvent {
-                        entrance: *entrance,
-                        // player: player_entity,
-                    });
-                }
+    for (actor, position, in_entrance) in actors.iter() {
+        let current = in_entrance.map(|in_entrance| in_entrance.0);
+        let found = entrances
+            .iter()
+            .find(|(_, entrance_pos, _)| entrance_pos.contains(*position));
+
+        if current == found.map(|(entrance, _, _)| entrance) {
+            continue;
+        }
+
+        if let Some(entrance) = current {
+            commands.entity(actor).remove::<InEntrance>();
+            if let Ok((_, _, building)) = entrances.get(entrance) {
+                trigger_exit(&mut commands, actor, entrance, building);
             }
         }
+
+        if let Some((entrance, _, building)) = found {
+            info!("{:?} entered building: {}", actor, building.id);
+            commands.entity(actor).insert(InEntrance(entrance));
+            commands.trigger(EnteredBuildingEvent {
+                actor,
+                entrance,
+                building: building.clone(),
+            });
+            commands.trigger(EntranceEnteredEvent {
+                entrance,
+                actor,
+                building: building.clone(),
+            });
+        }
     }
 }
 
-fn handle_player_entering_building(
-    player: Single<(&BuildingEntrance, Entity), (With<Player>, Added<BuildingEntrance>)>,
-    mut commands: Commands,
+/// Triggers the exit events for `actor` leaving `entrance`. The caller removes its
+/// [`InEntrance`].
+pub(crate) fn trigger_exit(
+    commands: &mut Commands,
+    actor: Entity,
+    entrance: Entity,
+    building: &BuildingId,
 ) {
-    let (entrance, entity) = *player;
-    info!("Player entered building: {:?}", entrance);
-    commands.trigger(PlayerEnteredBuildingEvent {
-        entrance: *entrance,
-        // player: entity,
+    info!("{:?} exited building: {}", actor, building.id);
+    commands.trigger(ExitedBuildingEvent {
+        actor,
+        entrance,
+        building: building.clone(),
+    });
+    commands.trigger(EntranceExitedEvent {
+        entrance,
+        actor,
+        building: building.clone(),
     });
 }
 
-fn player_exiting_building_observer(
-    trigger: On<PlayerExitedBuildingEvent>,
-    player: Single<Entity, With<Player>>,
+/// Spawning zones from tile coordinates, for built buildings, debug tools and tests.
+pub trait SpawnZoneExt {
+    /// Spawns a zone covering `rect`. `bundle` must carry the marker that gives the zone
+    /// its meaning (e.g. [`Solid`](crate::components::Solid)); the plugin handling that
+    /// marker turns the rect into a [`TileGroup`] exactly like a Tiled zone. An empty or
+    /// out of range `rect` is skipped with a warning, leaving `bundle` without a zone.
+    fn spawn_zone(&mut self, rect: TileRect, bundle: impl Bundle) -> EntityCommands<'_>;
+
+    /// Spawns an entrance to `building`, validated against the [`BuildingRegistry`] like
+    /// the entrances placed in the map.
+    fn spawn_entrance(&mut self, building: BuildingId, rect: TileRect) -> EntityCommands<'_>;
+}
+
+impl SpawnZoneExt for Commands<'_, '_> {
+    fn spawn_zone(&mut self, rect: TileRect, bundle: impl Bundle) -> EntityCommands<'_> {
+        if let Err(issue) = rect_to_tile_group(&rect) {
+            warn!("Skipping zone {:?}: {}", rect, issue);
+            return self.spawn(bundle);
+        }
+        self.spawn((rect, bundle))
+    }
+
+    fn spawn_entrance(&mut self, building: BuildingId, rect: TileRect) -> EntityCommands<'_> {
+        let name = Name::new(format!("{} entrance", building.id));
+        self.spawn_zone(rect, (name, building))
+    }
+}
+
+/// Validates and snaps every entrance again when the building registry is reloaded, so
+/// edited building files are checked against the map without a restart.
+fn resnap_entrances(
     mut commands: Commands,
+    registry: Res<BuildingRegistry>,
+    entrances: Query<Entity, (With<BuildingId>, With<TileGroup>)>,
 ) {
-    let entity = player.entity();
-    info!("Player exited building: {:?}", trigger.event().entrance);
-    commands.entity(entity).remove::<BuildingEntrance>();
+    if !registry.is_changed() || registry.is_added() {
+        return;
+    }
+    for entity in entrances.iter() {
+        commands.entity(entity).remove::<TileGroup>();
+    }
 }
 
+/// Snaps building zones placed in Tiled or spawned from Rust to the tile grid.
+///
+/// Zones reference a building either through the core [`BuildingEntrance`] enum or a
+/// data-only [`BuildingId`]; both are resolved to a [`BuildingId`] and checked against the
+/// [`BuildingRegistry`], so this waits until the registry has loaded.
 fn validate_and_snap_entrances(
     mut commands: Commands,
+    registry: Res<BuildingRegistry>,
+    grids: MapGrids,
     query: Query<
-        (Entity, &Transform, &BuildingEntrance, &TiledObject),
-        (Added<BuildingEntrance>, Without<TileGroup>),
+        (
+            Entity,
+            Option<&Transform>,
+            Option<&TiledObject>,
+            Option<&TileRect>,
+            Option<&BuildingEntrance>,
+            Option<&BuildingId>,
+        ),
+        (
+            Or<(With<BuildingEntrance>, With<BuildingId>)>,
+            Or<(With<TiledObject>, With<TileRect>)>,
+            Without<TileGroup>,
+        ),
     >,
 ) {
-    const TILE_SIZE: f32 = 32.0;
-    const EPSILON: f32 = 0.05;
-
-    for (entity, transform, entrance, tiled_object) in query.iter() {
-        let x = transform.translation.x;
-        let y = transform.translation.y;
-
-        let rem_x = x.rem_euclid(TILE_SIZE);
-        let rem_y = y.rem_euclid(TILE_SIZE);
-
-        let dist_x = rem_x.min(TILE_SIZE - rem_x);
-        let dist_y = rem_y.min(TILE_SIZE - rem_y);
-
-        if dist_x >= EPSILON || dist_y >= EPSILON {
+    for (entity, transform, tiled_object, rect, entrance, building_id) in query.iter() {
+        let Some(building) = building_id
+            .cloned()
+            .or_else(|| entrance.and_then(|entrance| entrance.building_id()))
+        else {
             panic!(
-                "\n❌ MAP INTEGRITY ERROR ❌\nObject: '{:?}'\nPosition: [x:{:.2}, y:{:.2}]\nIssue: Not aligned to {}-pixel grid.\n",
-                entrance, x, y, TILE_SIZE
-            );
-        }
-
-        let TiledObject::Rectangle { width, height } = tiled_object else {
-            panic!(
-                "\n❌ MAP INTEGRITY ERROR ❌\nObject: '{:?}'\nIssue: Unsupported TiledObject type for size validation.\n",
+                "\n❌ MAP INTEGRITY ERROR ❌\nObject: '{:?}'\nIssue: Entrance does not reference a building.\n",
                 entrance
             );
         };
+        let entrance = &building.id;
 
-        if width % TILE_SIZE != 0.0 || height % TILE_SIZE != 0.0 {
+        let Some(definition) = registry.get(entrance) else {
             panic!(
-                "\n❌ MAP INTEGRITY ERROR ❌\nObject: '{:?}'\nSize: [w:{}, h:{}]\nIssue: Dimensions are not multiples of tile size ({}).\n",
-                entrance, width, height, TILE_SIZE
+                "\n❌ MAP INTEGRITY ERROR ❌\nObject: '{}'\nIssue: Unknown building id. Known ids: {:?}\n",
+                entrance,
+                registry.ids().collect::<Vec<_>>()
             );
-        }
+        };
+        let interactable = Interactable {
+            prompt: format!("enter {}", definition.display_name),
+        };
 
-        let adjusted_y = y - height;
+        let grid = grids.of(entity);
+        let Some(tile_group) = zone_tile_group(entrance, grid, transform, tiled_object, rect)
+        else {
+            continue;
+        };
+        info!("Inserting TileGroup: {:?}", tile_group);
 
-        let start_grid_x = (x / TILE_SIZE).round() as u32;
-        let start_grid_y = (adjusted_y / TILE_SIZE).round() as u32;
+        commands
+            .entity(entity)
+            .insert((tile_group, building))
+            // Keep a custom prompt set on the Tiled object.
+            .insert_if_new(interactable);
+    }
+}
 
-        let width_in_tiles = (width / TILE_SIZE).round() as u32;
-        let height_in_tiles = (height / TILE_SIZE).round() as u32;
+/// The [`TileGroup`] of a zone, from its [`TileRect`] if spawned from Rust or else from
+/// its Tiled object and the `grid` of its map. `None` while that grid is still unknown.
+///
+/// Panics with a map integrity error naming `label` if a Tiled zone is invalid. Invalid
+/// [`TileRect`]s come from code rather than map data, so they are only warned about.
+pub(crate) fn zone_tile_group(
+    label: &str,
+    grid: Option<&TileGrid>,
+    transform: Option<&Transform>,
+    tiled_object: Option<&TiledObject>,
+    rect: Option<&TileRect>,
+) -> Option<TileGroup> {
+    if let Some(rect) = rect {
+        return match rect_to_tile_group(rect) {
+            Ok(tile_group) => Some(tile_group),
+            Err(issue) => {
+                warn_once!("Skipping zone '{}' at {:?}: {}", label, rect, issue);
+                None
+            }
+        };
+    }
 
-        let tile_group = TileGroup::Rectangle(RectangleTileGroup {
-            bottom_left: TilePosition {
-                x: start_grid_x,
-                y: start_grid_y,
-            },
-            top_right: TilePosition {
-                x: start_grid_x + width_in_tiles - 1,
-                y: start_grid_y + height_in_tiles - 1,
-            },
-        });
-        info!("Inserting TileGroup: {:?}", tile_group);
+    let (Some(transform), Some(tiled_object)) = (transform, tiled_object) else {
+        panic!(
+            "\n❌ MAP INTEGRITY ERROR ❌\nObject: '{}'\nIssue: Zone has neither a TileRect nor a Tiled object.\n",
+            label
+        );
+    };
+    Some(snap_to_tile_group(label, grid?, transform, tiled_object))
+}
+
+/// Converts a [`TileRect`] into the [`TileGroup`] it covers, or says why it can't.
+fn rect_to_tile_group(rect: &TileRect) -> Result<TileGroup, &'static str> {
+    if rect.width == 0 || rect.height == 0 {
+        return Err("Zone must cover at least one tile.");
+    }
+
+    let top_right = rect
+        .bottom_left
+        .x
+        .checked_add(rect.width - 1)
+        .zip(rect.bottom_left.y.checked_add(rect.height - 1));
+    let Some((right, top)) = top_right else {
+        return Err("Zone extends past the end of the grid.");
+    };
+
+    Ok(TileGroup::Rectangle(RectangleTileGroup {
+        bottom_left: rect.bottom_left,
+        top_right: TilePosition { x: right, y: top },
+    }))
+}
+
+/// Converts a grid-aligned Tiled rectangle into the [`TileGroup`] it covers on `grid`.
+///
+/// Panics with a map integrity error naming `label` if the object is misaligned or not a
+/// rectangle.
+fn snap_to_tile_group(
+    label: &str,
+    grid: &TileGrid,
+    transform: &Transform,
+    tiled_object: &TiledObject,
+) -> TileGroup {
+    let origin = transform.translation.truncate();
+
+    let TiledObject::Rectangle { width, height } = tiled_object else {
+        panic!(
+            "\n❌ MAP INTEGRITY ERROR ❌\nObject: '{}'\nIssue: Unsupported TiledObject type for size validation.\n",
+            label
+        );
+    };
+
+    match grid
+        .snap_rect(origin, Vec2::new(*width, *height))
+        .and_then(|rect| rect_to_tile_group(&rect).map_err(String::from))
+    {
+        Ok(tile_group) => tile_group,
+        Err(issue) => panic!(
+            "\n❌ MAP INTEGRITY ERROR ❌\nObject: '{}'\nPosition: [x:{:.2}, y:{:.2}]\nSize: [w:{}, h:{}]\nIssue: {}\n",
+            label, origin.x, origin.y, width, height, issue
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    const TEST_MAP: &str = "test/entrance_test.tmx";
+
+    fn entrance_tiles(app: &mut TestApp) -> Vec<TilePosition> {
+        let world = app.world();
+        world
+            .query_filtered::<&TileGroup, With<BuildingEntrance>>()
+            .single(world)
+            .expect("the test map has one entrance")
+            .tiles()
+            .collect()
+    }
+
+    #[test]
+    fn tiled_entrance_snaps_to_its_rectangle() {
+        let mut app = TestApp::new(TEST_MAP);
+        app.wait_for_zones(1);
+
+        assert_eq!(
+            entrance_tiles(&mut app),
+            vec![TilePosition { x: 3, y: 3 }, TilePosition { x: 4, y: 3 }]
+        );
+
+        let world = app.world();
+        let building = world
+            .query_filtered::<&BuildingId, With<BuildingEntrance>>()
+            .single(world)
+            .unwrap();
+        assert_eq!(building.id, "nutrition_house");
+    }
+
+    #[test]
+    fn entrance_snaps_to_non_square_tiles() {
+        let mut app = TestApp::new("test/wide_tiles_test.tmx");
+        app.wait_for_zones(1);
+
+        assert_eq!(
+            entrance_tiles(&mut app),
+            vec![TilePosition { x: 2, y: 4 }, TilePosition { x: 3, y: 4 }]
+        );
+    }
+
+    #[test]
+    fn walking_into_nutrition_house_triggers_exactly_one_enter_event() {
+        let mut app = TestApp::new(TEST_MAP);
+        app.record::<EnteredBuildingEvent>();
+        app.record::<EntranceEnteredEvent>();
+        app.record::<ExitedBuildingEvent>();
+        app.wait_for_zones(1);
+
+        app.walk(KeyCode::KeyD, 3);
+        app.walk(KeyCode::KeyW, 3);
+        assert_eq!(app.player_tile(), TilePosition { x: 3, y: 3 });
+
+        // Standing (and stepping) inside the zone must not re-trigger.
+        app.step(20);
+        app.walk(KeyCode::KeyD, 1);
+        assert_eq!(app.count::<EnteredBuildingEvent>(), 1);
+        assert_eq!(app.count::<EntranceEnteredEvent>(), 1);
+        assert_eq!(app.count::<ExitedBuildingEvent>(), 0);
+
+        let player = app.player();
+        assert!(app.world().get::<InEntrance>(player).is_some());
+
+        app.walk(KeyCode::KeyW, 1);
+        assert_eq!(app.count::<ExitedBuildingEvent>(), 1);
+        assert!(app.world().get::<InEntrance>(player).is_none());
+    }
+
+    #[test]
+    fn spawned_entrance_behaves_like_a_tiled_one() {
+        let mut app = TestApp::new(TEST_MAP);
+        app.record::<EnteredBuildingEvent>();
+        let world = app.world();
+        world.commands().spawn_entrance(
+            BuildingId::new("nutrition_house"),
+            TileRect::new(TilePosition { x: 1, y: 0 }, 1, 1),
+        );
+        world.flush();
+        app.wait_for_zones(2);
+
+        app.walk(KeyCode::KeyD, 1);
+        assert_eq!(app.count::<EnteredBuildingEvent>(), 1);
+
+        let player = app.player();
+        let world = app.world();
+        let entrance = world.get::<InEntrance>(player).unwrap().0;
+        assert!(world.get::<Interactable>(entrance).is_some());
+    }
+
+    #[test]
+    fn empty_spawned_zone_is_skipped_instead_of_panicking() {
+        let mut app = TestApp::new(TEST_MAP);
+        let world = app.world();
+        let entrance = world
+            .commands()
+            .spawn_entrance(
+                BuildingId::new("nutrition_house"),
+                TileRect::new(TilePosition { x: 1, y: 0 }, 0, 1),
+            )
+            .id();
+        world.flush();
+        app.wait_for_zones(1);
+        app.step(5);
 
-        commands.entity(entity).insert(tile_group);
+        let world = app.world();
+        assert!(world.get::<TileRect>(entrance).is_none());
+        assert!(world.get::<TileGroup>(entrance).is_none());
     }
 }
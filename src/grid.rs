@@ -0,0 +1,362 @@
+//! Conversion between tile coordinates and world positions for each supported map
+//! orientation.
+//!
+//! Tile coordinates always grow right and up from the bottom-left of the map, so movement,
+//! zones, pathfinding and the camera work the same on any grid; only the placement of
+//! tiles in the world differs.
+
+use std::fmt::Debug;
+
+use bevy::prelude::*;
+
+use crate::components::{TilePosition, TileRect};
+
+/// How far, in tiles, a Tiled object may be off the grid and still snap to it.
+const GRID_EPSILON: f32 = 0.002;
+
+/// The shape of a map's grid.
+pub trait GridGeometry: Debug + Send + Sync + 'static {
+    /// World position of the center of `position`.
+    fn tile_center(&self, position: TilePosition) -> Vec2;
+
+    /// The tile containing `world`, or `None` outside the grid.
+    fn world_to_tile(&self, world: Vec2) -> Option<TilePosition>;
+
+    /// Size of the bounding box of a single tile, in world pixels.
+    fn tile_size(&self) -> Vec2;
+
+    /// The tiles covered by a Tiled rectangle object with its top-left corner at `origin`
+    /// (world space) and `size` in Tiled pixels, or why it doesn't line up with the grid.
+    fn snap_rect(&self, origin: Vec2, size: Vec2) -> Result<TileRect, String>;
+
+    /// World-space bounding box of every tile in `rect`.
+    fn rect_bounds(&self, rect: TileRect) -> Rect {
+        rect.tiles()
+            .map(|tile| Rect::from_center_size(self.tile_center(tile), self.tile_size()))
+            .reduce(|bounds, tile| bounds.union(tile))
+            .unwrap_or_default()
+    }
+}
+
+/// The geometry of a loaded map, read from the map file. Lives on the map entity.
+#[derive(Component, Debug, Deref)]
+pub struct TileGrid(Box<dyn GridGeometry>);
+
+impl TileGrid {
+    pub fn new(geometry: impl GridGeometry) -> Self {
+        Self(Box::new(geometry))
+    }
+}
+
+/// A [`TileRect`] from the grid coordinates of its bottom-left corner and its size in
+/// tiles, which must both be whole numbers.
+fn aligned_rect(bottom_left: Vec2, size: Vec2) -> Result<TileRect, String> {
+    let is_whole = |value: Vec2| (value - value.round()).abs().max_element() < GRID_EPSILON;
+    if !is_whole(bottom_left) {
+        return Err("Not aligned to the tile grid.".to_string());
+    }
+    if !is_whole(size) {
+        return Err("Dimensions are not whole tiles.".to_string());
+    }
+    if bottom_left.round().min_element() < 0.0 {
+        return Err("Extends past the edge of the grid.".to_string());
+    }
+
+    let bottom_left = bottom_left.round();
+    let size = size.round();
+    Ok(TileRect::new(
+        TilePosition {
+            x: bottom_left.x as u32,
+            y: bottom_left.y as u32,
+        },
+        size.x as u32,
+        size.y as u32,
+    ))
+}
+
+fn floor_to_tile(grid: Vec2) -> Option<TilePosition> {
+    let tile = grid.floor();
+    (tile.x >= 0.0 && tile.y >= 0.0).then(|| TilePosition {
+        x: tile.x as u32,
+        y: tile.y as u32,
+    })
+}
+
+/// Square or rectangular tiles in rows and columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrthogonalGrid {
+    pub tile_size: Vec2,
+}
+
+impl GridGeometry for OrthogonalGrid {
+    fn tile_center(&self, position: TilePosition) -> Vec2 {
+        (UVec2::new(position.x, position.y).as_vec2() + 0.5) * self.tile_size
+    }
+
+    fn world_to_tile(&self, world: Vec2) -> Option<TilePosition> {
+        floor_to_tile(world / self.tile_size)
+    }
+
+    fn tile_size(&self) -> Vec2 {
+        self.tile_size
+    }
+
+    fn snap_rect(&self, origin: Vec2, size: Vec2) -> Result<TileRect, String> {
+        let bottom_left = Vec2::new(origin.x, origin.y - size.y);
+        aligned_rect(bottom_left / self.tile_size, size / self.tile_size)
+    }
+}
+
+/// Diamond-shaped tiles. The x axis runs up and to the right, the y axis up and to the
+/// left, and the map's bottom corner sits at `y = 0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IsometricGrid {
+    pub tile_size: Vec2,
+    /// Map height in tiles, which shifts the grid right so the map's left corner is at
+    /// `x = 0`.
+    pub height: u32,
+}
+
+impl IsometricGrid {
+    fn grid_to_world(&self, grid: Vec2) -> Vec2 {
+        Vec2::new(grid.x - grid.y + self.height as f32, grid.x + grid.y) * self.tile_size / 2.0
+    }
+
+    fn world_to_grid(&self, world: Vec2) -> Vec2 {
+        let diagonal = world / (self.tile_size / 2.0);
+        let across = diagonal.x - self.height as f32;
+        Vec2::new(diagonal.y + across, diagonal.y - across) / 2.0
+    }
+}
+
+impl GridGeometry for IsometricGrid {
+    fn tile_center(&self, position: TilePosition) -> Vec2 {
+        self.grid_to_world(UVec2::new(position.x, position.y).as_vec2() + 0.5)
+    }
+
+    fn world_to_tile(&self, world: Vec2) -> Option<TilePosition> {
+        floor_to_tile(self.world_to_grid(world))
+    }
+
+    fn tile_size(&self) -> Vec2 {
+        self.tile_size
+    }
+
+    /// Tiled measures isometric objects along the grid axes, one tile height per tile.
+    fn snap_rect(&self, origin: Vec2, size: Vec2) -> Result<TileRect, String> {
+        let top_left = self.world_to_grid(origin);
+        let size = size / self.tile_size.y;
+        aligned_rect(Vec2::new(top_left.x, top_left.y - size.y), size)
+    }
+}
+
+/// Hexagons in offset rows (pointy-top) or offset columns (flat-top).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HexagonalGrid {
+    pub tile_size: Vec2,
+    /// Length of the hexagon's flat sides along the stagger axis, in pixels.
+    pub side_length: f32,
+    /// Whether columns rather than rows are staggered.
+    pub stagger_columns: bool,
+    /// Whether odd rather than even rows (or columns), counted from the bottom, are
+    /// shifted by half a tile.
+    pub shift_odd: bool,
+}
+
+impl HexagonalGrid {
+    fn is_shifted(&self, index: u32) -> bool {
+        (index % 2 == 1) == self.shift_odd
+    }
+
+    /// Distance between the centers of neighbouring columns and rows.
+    fn spacing(&self) -> Vec2 {
+        if self.stagger_columns {
+            Vec2::new(
+                (self.tile_size.x + self.side_length) / 2.0,
+                self.tile_size.y,
+            )
+        } else {
+            Vec2::new(
+                self.tile_size.x,
+                (self.tile_size.y + self.side_length) / 2.0,
+            )
+        }
+    }
+
+    /// Tiles whose cells may contain `world`, ignoring the stagger.
+    fn candidates(&self, world: Vec2) -> impl Iterator<Item = TilePosition> {
+        let guess = ((world - self.tile_size / 2.0) / self.spacing())
+            .round()
+            .as_ivec2();
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| guess + IVec2::new(dx, dy)))
+            .filter(|tile| tile.min_element() >= 0)
+            .map(|tile| TilePosition {
+                x: tile.x as u32,
+                y: tile.y as u32,
+            })
+    }
+}
+
+impl GridGeometry for HexagonalGrid {
+    fn tile_center(&self, position: TilePosition) -> Vec2 {
+        let mut center =
+            UVec2::new(position.x, position.y).as_vec2() * self.spacing() + self.tile_size / 2.0;
+        if self.stagger_columns && self.is_shifted(position.x) {
+            center.y += self.tile_size.y / 2.0;
+        } else if !self.stagger_columns && self.is_shifted(position.y) {
+            center.x += self.tile_size.x / 2.0;
+        }
+        center
+    }
+
+    /// The tile with the nearest center, as long as `world` is within its bounding box.
+    fn world_to_tile(&self, world: Vec2) -> Option<TilePosition> {
+        self.candidates(world)
+            .min_by(|a, b| {
+                let a = self.tile_center(*a).distance_squared(world);
+                let b = self.tile_center(*b).distance_squared(world);
+                a.total_cmp(&b)
+            })
+            .filter(|tile| {
+                Rect::from_center_size(self.tile_center(*tile), self.tile_size).contains(world)
+            })
+    }
+
+    fn tile_size(&self) -> Vec2 {
+        self.tile_size
+    }
+
+    /// Hexagons can't line up with a rectangle, so this takes every tile whose center lies
+    /// inside it, rounded out to a rectangle of tiles.
+    fn snap_rect(&self, origin: Vec2, size: Vec2) -> Result<TileRect, String> {
+        let area = Rect::new(origin.x, origin.y - size.y, origin.x + size.x, origin.y);
+        let corners = [
+            area.min,
+            area.max,
+            Vec2::new(area.min.x, area.max.y),
+            Vec2::new(area.max.x, area.min.y),
+        ];
+        let search = corners
+            .into_iter()
+            .flat_map(|corner| self.candidates(corner))
+            .map(|tile| UVec2::new(tile.x, tile.y));
+        let (search_min, search_max) = search
+            .fold((UVec2::MAX, UVec2::ZERO), |(min, max), tile| {
+                (min.min(tile), max.max(tile))
+            });
+
+        let covered: Vec<_> = (search_min.y..=search_max.y)
+            .flat_map(|y| (search_min.x..=search_max.x).map(move |x| UVec2::new(x, y)))
+            .filter(|tile| {
+                area.contains(self.tile_center(TilePosition {
+                    x: tile.x,
+                    y: tile.y,
+                }))
+            })
+            .collect();
+        if covered.is_empty() {
+            return Err("Covers no tile centers.".to_string());
+        }
+
+        let min = covered.iter().fold(UVec2::MAX, |min, tile| min.min(*tile));
+        let max = covered.iter().fold(UVec2::ZERO, |max, tile| max.max(*tile));
+        Ok(TileRect::new(
+            TilePosition { x: min.x, y: min.y },
+            max.x - min.x + 1,
+            max.y - min.y + 1,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trips(grid: &dyn GridGeometry) {
+        for y in 0..6 {
+            for x in 0..6 {
+                let tile = TilePosition { x, y };
+                assert_eq!(
+                    grid.world_to_tile(grid.tile_center(tile)),
+                    Some(tile),
+                    "{:?} in {:?}",
+                    tile,
+                    grid
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tile_centers_map_back_to_their_tiles() {
+        round_trips(&OrthogonalGrid {
+            tile_size: Vec2::new(48.0, 24.0),
+        });
+        round_trips(&IsometricGrid {
+            tile_size: Vec2::new(64.0, 32.0),
+            height: 10,
+        });
+        for (stagger_columns, shift_odd) in [(false, false), (false, true), (true, false)] {
+            round_trips(&HexagonalGrid {
+                tile_size: Vec2::new(28.0, 32.0),
+                side_length: 16.0,
+                stagger_columns,
+                shift_odd,
+            });
+        }
+    }
+
+    #[test]
+    fn isometric_axes_run_diagonally() {
+        let grid = IsometricGrid {
+            tile_size: Vec2::new(64.0, 32.0),
+            height: 10,
+        };
+        let origin = grid.tile_center(TilePosition { x: 0, y: 0 });
+
+        assert_eq!(origin, Vec2::new(320.0, 16.0));
+        assert_eq!(
+            grid.tile_center(TilePosition { x: 1, y: 0 }) - origin,
+            Vec2::new(32.0, 16.0)
+        );
+        assert_eq!(
+            grid.tile_center(TilePosition { x: 0, y: 1 }) - origin,
+            Vec2::new(-32.0, 16.0)
+        );
+    }
+
+    #[test]
+    fn isometric_objects_snap_along_the_grid_axes() {
+        let grid = IsometricGrid {
+            tile_size: Vec2::new(64.0, 32.0),
+            height: 10,
+        };
+        // Top-left corner of tile (2, 4), covering 2x1 tiles in Tiled units.
+        let origin = grid.grid_to_world(Vec2::new(2.0, 5.0));
+
+        let rect = grid.snap_rect(origin, Vec2::new(64.0, 32.0)).unwrap();
+        assert_eq!(rect.bottom_left, TilePosition { x: 2, y: 4 });
+        assert_eq!((rect.width, rect.height), (2, 1));
+        assert!(grid.snap_rect(origin + 5.0, Vec2::splat(32.0)).is_err());
+    }
+
+    #[test]
+    fn hexagonal_objects_cover_the_tiles_centered_inside() {
+        let grid = HexagonalGrid {
+            tile_size: Vec2::new(28.0, 32.0),
+            side_length: 16.0,
+            stagger_columns: false,
+            shift_odd: true,
+        };
+        let area = grid
+            .rect_bounds(TileRect::new(TilePosition { x: 1, y: 1 }, 2, 2))
+            .inflate(-4.0);
+
+        let rect = grid
+            .snap_rect(Vec2::new(area.min.x, area.max.y), area.size())
+            .unwrap();
+        assert_eq!(rect.bottom_left, TilePosition { x: 1, y: 1 });
+        assert_eq!((rect.width, rect.height), (2, 2));
+    }
+}
@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{
+        Facing, Interactable, InteractionTarget, Player, PlayerMovementSet, TileGroup, TilePosition,
+    },
+    dialogue::{ActiveDialogue, advance_dialogue},
+};
+
+const INTERACT_KEY: KeyCode = KeyCode::KeyE;
+const INTERACT_BUTTON: GamepadButton = GamepadButton::South;
+
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveInputDevice>()
+            .add_systems(Startup, spawn_interaction_prompt)
+            .add_systems(
+                Update,
+                (
+                    detect_input_device,
+                    update_interaction_target,
+                    // Otherwise the key that closes a conversation would reopen it.
+                    trigger_interaction.before(advance_dialogue),
+                    update_interaction_prompt,
+                )
+                    .chain()
+                    .after(PlayerMovementSet),
+            );
+    }
+}
+
+/// The input device the player used most recently, used to pick button hints.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ActiveInputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+    Touch,
+}
+
+impl ActiveInputDevice {
+    fn hint(self, prompt: &str) -> String {
+        match self {
+            ActiveInputDevice::Keyboard => format!("Press E to {}", prompt),
+            ActiveInputDevice::Gamepad => format!("Press A to {}", prompt),
+            ActiveInputDevice::Touch => format!("Tap to {}", prompt),
+        }
+    }
+}
+
+/// Triggered when `actor` uses the Interact action on the `target` zone.
+#[derive(Debug, EntityEvent)]
+pub struct Interacted {
+    #[event_target]
+    pub target: Entity,
+    pub actor: Entity,
+}
+
+#[derive(Component)]
+struct InteractionPrompt;
+
+fn spawn_interaction_prompt(mut commands: Commands) {
+    commands
+        .spawn((
+            InteractionPrompt,
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: px(160),
+                left: percent(50),
+                padding: UiRect::axes(px(16), px(8)),
+                display: Display::None,
+                ..default()
+            },
+            UiTransform::from_translation(Val2::percent(-50.0, 0.0)),
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        ))
+        .with_child(Text::new(""));
+}
+
+fn detect_input_device(
+    mut device: ResMut<ActiveInputDevice>,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    touches: Res<Touches>,
+) {
+    let latest = if touches.any_just_pressed() {
+        ActiveInputDevice::Touch
+    } else if gamepads
+        .iter()
+        .any(|gamepad| gamepad.get_just_pressed().next().is_some())
+    {
+        ActiveInputDevice::Gamepad
+    } else if kb_input.get_just_pressed().next().is_some() {
+        ActiveInputDevice::Keyboard
+    } else {
+        return;
+    };
+
+    device.set_if_neq(latest);
+}
+
+/// Targets the interactable zone under the player, or else the zone or NPC directly in front.
+fn update_interaction_target(
+    player: Single<(&TilePosition, &Facing, &mut InteractionTarget), With<Player>>,
+    interactables: Query<(Entity, &TileGroup), With<Interactable>>,
+    npcs: Query<(Entity, &TilePosition), (With<Interactable>, Without<Player>)>,
+) {
+    let (position, facing, mut target) = player.into_inner();
+
+    let zone_at = |position: TilePosition| {
+        interactables
+            .iter()
+            .find(|(_, group)| group.contains(position))
+            .map(|(entity, _)| entity)
+            .or_else(|| {
+                npcs.iter()
+                    .find(|(_, npc)| **npc == position)
+                    .map(|(entity, _)| entity)
+            })
+    };
+    let found = zone_at(*position).or_else(|| facing.step(*position).and_then(zone_at));
+
+    if target.0 != found {
+        target.0 = found;
+    }
+}
+
+fn trigger_interaction(
+    player: Single<(Entity, &InteractionTarget), With<Player>>,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    prompt: Query<&Interaction, (Changed<Interaction>, With<InteractionPrompt>)>,
+    dialogue: Res<ActiveDialogue>,
+    mut commands: Commands,
+) {
+    let (actor, target) = *player;
+    let Some(target) = target.0 else {
+        return;
+    };
+    // The interact key advances the conversation instead.
+    if dialogue.is_open() {
+        return;
+    }
+
+    let pressed = kb_input.just_pressed(INTERACT_KEY)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(INTERACT_BUTTON))
+        || prompt
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed);
+
+    if pressed {
+        info!("Player interacted with {:?}", target);
+        commands.trigger(Interacted { target, actor });
+    }
+}
+
+/// Shows the button hint for the current target and input device.
+fn update_interaction_prompt(
+    player: Single<Ref<InteractionTarget>, With<Player>>,
+    device: Res<ActiveInputDevice>,
+    interactables: Query<&Interactable>,
+    prompt: Single<(&mut Node, &Children), With<InteractionPrompt>>,
+    mut texts: Query<&mut Text>,
+) {
+    if !player.is_changed() && !device.is_changed() {
+        return;
+    }
+
+    let (mut node, children) = prompt.into_inner();
+    let interactable = player.0.and_then(|target| interactables.get(target).ok());
+
+    let Some(interactable) = interactable else {
+        node.display = Display::None;
+        return;
+    };
+
+    node.display = Display::Flex;
+    for child in children.iter() {
+        if let Ok(mut text) = texts.get_mut(child) {
+            **text = device.hint(&interactable.prompt);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::{
+    ecs::{entity_disabling::Disabled, query::Allow},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    clock::{GameClock, OfflineCatchUp, deserialize_time_of_day},
+    collision::CollisionMap,
+    components::{
+        ActorIntentSet, BuildingId, Dialogue, Facing, Interactable, MoveIntent, MovementCooldown,
+        TileGroup, TilePosition, YSort,
+    },
+    data::JsonAssetAppExt,
+    map::HomeMap,
+    pathfinding::find_path,
+    sprite_animation::SpriteAnimator,
+    weather::{SeeksShelter, WeatherState},
+};
+
+/// Upper bound on tiles visited per path search.
+const MAX_PATH_SEARCH: usize = 4096;
+/// Offline time longer than this puts keepers where their schedule says they should be
+/// instead of having them walk there.
+const CATCH_UP_THRESHOLD_MINUTES: f64 = 30.0;
+/// Seconds between two keeper steps unless the roster says otherwise.
+const DEFAULT_STEP_COOLDOWN: f32 = 0.3;
+/// Seconds a keeper waits for its way to clear before planning again.
+const REPLAN_DELAY_SECONDS: f32 = 0.5;
+/// Blocked plans in a row after which a keeper gives up until its next schedule entry.
+const MAX_FAILED_PLANS: u32 = 5;
+
+pub struct KeeperPlugin;
+
+impl Plugin for KeeperPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_json_asset::<KeeperRoster>(&["keepers.json"])
+            .add_systems(Startup, load_keeper_roster)
+            .add_systems(Update, (spawn_keepers, park_keepers).chain())
+            .add_systems(FixedUpdate, follow_schedules.in_set(ActorIntentSet));
+    }
+}
+
+/// Sanctuary staff and their daily schedules, loaded from a `*.keepers.json` file.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct KeeperRoster {
+    pub keepers: Vec<KeeperDefinition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeeperDefinition {
+    pub name: String,
+    pub spawn: [u32; 2],
+    /// Seconds between two steps, like the player's `step_cooldown`.
+    #[serde(default)]
+    pub step_cooldown: Option<f32>,
+    /// Script started when the player talks to the keeper.
+    #[serde(default)]
+    pub dialogue: Option<String>,
+    pub schedule: Vec<ScheduleEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduleEntry {
+    /// Time of day the activity starts, as `"HH:MM"`.
+    #[serde(deserialize_with = "deserialize_time_of_day")]
+    pub at: u32,
+    pub activity: String,
+    pub destination: Destination,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Destination {
+    /// The entrance of a building from the registry.
+    Building(String),
+    Tile([u32; 2]),
+}
+
+#[derive(Debug)]
+pub enum KeeperState {
+    /// Needs a path to the active entry's destination.
+    Idle,
+    Walking {
+        path: VecDeque<TilePosition>,
+    },
+    Working,
+    /// Waiting out bad weather under [`SeeksShelter`]; the schedule resumes afterwards.
+    Sheltering,
+    /// The path is blocked, e.g. by another actor; plan again once `retry` finishes.
+    Blocked {
+        retry: Timer,
+    },
+    /// The destination is unreachable; wait for the next schedule entry.
+    Stuck,
+}
+
+#[derive(Component)]
+pub struct Keeper {
+    schedule: Vec<ScheduleEntry>,
+    active_entry: Option<usize>,
+    pub state: KeeperState,
+    /// Plans that ran into a blocked tile since the keeper last arrived.
+    failed_plans: u32,
+    /// Skip straight to the active entry's destination on the next plan.
+    catch_up: bool,
+}
+
+impl Keeper {
+    /// The entry in effect at `minute_of_day`; before the first entry the previous day's
+    /// last one still applies.
+    fn entry_at(&self, minute_of_day: u32) -> Option<usize> {
+        self.schedule
+            .iter()
+            .rposition(|entry| entry.at <= minute_of_day)
+            .or_else(|| self.schedule.len().checked_sub(1))
+    }
+}
+
+#[derive(Resource)]
+struct KeeperRosterHandle(Handle<KeeperRoster>);
+
+fn load_keeper_roster(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(KeeperRosterHandle(
+        asset_server.load("data/sanctuary.keepers.json"),
+    ));
+}
+
+fn spawn_keepers(
+    mut commands: Commands,
+    roster_handle: Res<KeeperRosterHandle>,
+    rosters: Res<Assets<KeeperRoster>>,
+    asset_server: Res<AssetServer>,
+    home: Query<(), With<HomeMap>>,
+    keepers: Query<(), (With<Keeper>, Allow<Disabled>)>,
+    offline: Option<Res<OfflineCatchUp>>,
+) {
+    if home.is_empty() || !keepers.is_empty() {
+        return;
+    }
+    let Some(roster) = rosters.get(&roster_handle.0) else {
+        return;
+    };
+
+    let catch_up = offline.is_some_and(|offline| offline.minutes > CATCH_UP_THRESHOLD_MINUTES);
+    for definition in roster.keepers.iter() {
+        let mut schedule = definition.schedule.clone();
+        schedule.sort_by_key(|entry| entry.at);
+        let step_cooldown = match definition.step_cooldown {
+            // Also rejects NaN.
+            Some(seconds) if seconds >= 0.0 => seconds,
+            Some(seconds) => {
+                warn!(
+                    "{} has an invalid step_cooldown {}, using {}",
+                    definition.name, seconds, DEFAULT_STEP_COOLDOWN
+                );
+                DEFAULT_STEP_COOLDOWN
+            }
+            None => DEFAULT_STEP_COOLDOWN,
+        };
+
+        info!("Spawning keeper: {}", definition.name);
+        let mut keeper = commands.spawn((
+            Name::new(definition.name.clone()),
+            Keeper {
+                schedule,
+                active_entry: None,
+                state: KeeperState::Idle,
+                failed_plans: 0,
+                catch_up,
+            },
+            TilePosition {
+                x: definition.spawn[0],
+                y: definition.spawn[1],
+            },
+            MovementCooldown(Timer::from_seconds(step_cooldown, TimerMode::Once)),
+            MoveIntent::default(),
+            SeeksShelter::default(),
+            SpriteAnimator::new(asset_server.load("keeper.anim.json")),
+            Transform::default(),
+            YSort { foot_offset: -16. },
+        ));
+        if let Some(script) = &definition.dialogue {
+            keeper.insert((
+                Dialogue {
+                    script: script.clone(),
+                    on_enter: false,
+                },
+                Interactable {
+                    prompt: format!("talk to {}", definition.name),
+                },
+            ));
+        }
+    }
+}
+
+/// Keepers live on the home map but outside its hierarchy, so they outlast trips
+/// elsewhere. They are disabled while the player is away, and catch up on their schedule
+/// when the home map returns, like after time spent offline.
+fn park_keepers(
+    mut commands: Commands,
+    home: Query<(), With<HomeMap>>,
+    mut keepers: Query<(Entity, &mut Keeper, Has<Disabled>)>,
+) {
+    let at_home = !home.is_empty();
+    for (entity, mut keeper, disabled) in keepers.iter_mut() {
+        if at_home && disabled {
+            commands.entity(entity).remove::<Disabled>();
+            keeper.catch_up = true;
+        } else if !at_home && !disabled {
+            commands.entity(entity).insert(Disabled);
+        }
+    }
+}
+
+fn resolve_destination(
+    destination: &Destination,
+    entrances: &Query<(&TileGroup, &BuildingId)>,
+) -> Option<TilePosition> {
+    match destination {
+        Destination::Tile([x, y]) => Some(TilePosition { x: *x, y: *y }),
+        Destination::Building(id) => entrances
+            .iter()
+            .find(|(_, building)| building.id == *id)
+            .and_then(|(group, _)| group.tiles().next()),
+    }
+}
+
+/// Drives each keeper's state machine from the clock: pick the active schedule entry,
+/// path to its destination, then walk the path one [`MoveIntent`] at a time.
+///
+/// Paths go around the tiles other actors stand on; an actor stepping into the way
+/// blocks the keeper until it moves on.
+pub(crate) fn follow_schedules(
+    clock: Res<GameClock>,
+    collision_map: Res<CollisionMap>,
+    entrances: Query<(&TileGroup, &BuildingId)>,
+    mut keepers: Query<(
+        &Name,
+        &mut Keeper,
+        &mut TilePosition,
+        &mut MoveIntent,
+        Has<SeeksShelter>,
+    )>,
+    others: Query<&TilePosition, (With<MovementCooldown>, Without<Keeper>)>,
+    weather: Res<WeatherState>,
+    time: Res<Time>,
+) {
+    let occupied: HashSet<TilePosition> = others
+        .iter()
+        .copied()
+        .chain(keepers.iter().map(|(_, _, position, _, _)| *position))
+        .collect();
+
+    for (name, mut keeper, mut position, mut intent, seeks_shelter) in keepers.iter_mut() {
+        intent.0 = None;
+
+        // Shelter seeking steers the keeper instead; plan again once the weather clears.
+        if seeks_shelter && weather.current.seeks_shelter() {
+            keeper.state = KeeperState::Sheltering;
+            continue;
+        }
+
+        let Some(entry_index) = keeper.entry_at(clock.minute_of_day()) else {
+            continue;
+        };
+        let entry = keeper.schedule[entry_index].clone();
+        let entry_changed = keeper.active_entry != Some(entry_index);
+
+        if let KeeperState::Blocked { retry } = &mut keeper.state {
+            retry.tick(time.delta());
+            if retry.is_finished() {
+                keeper.state = KeeperState::Idle;
+            }
+        }
+
+        let needs_plan = matches!(keeper.state, KeeperState::Idle | KeeperState::Sheltering);
+        if entry_changed || keeper.catch_up || needs_plan {
+            // Buildings may not have been snapped yet; try again next frame.
+            let Some(goal) = resolve_destination(&entry.destination, &entrances) else {
+                continue;
+            };
+
+            if entry_changed {
+                info!("{} starts: {}", name, entry.activity);
+                keeper.failed_plans = 0;
+            }
+            keeper.active_entry = Some(entry_index);
+
+            if keeper.catch_up {
+                *position = goal;
+                keeper.catch_up = false;
+                keeper.state = KeeperState::Working;
+                continue;
+            }
+
+            let blocked = |tile: TilePosition| {
+                collision_map.is_blocked(tile) || (tile != goal && occupied.contains(&tile))
+            };
+            keeper.state = match find_path(*position, goal, blocked, MAX_PATH_SEARCH) {
+                Some(path) => KeeperState::Walking { path: path.into() },
+                None => {
+                    warn!("{} cannot reach {:?}", name, entry.destination);
+                    KeeperState::Stuck
+                }
+            };
+        }
+
+        let KeeperState::Walking { path } = &mut keeper.state else {
+            continue;
+        };
+
+        while path.front() == Some(&*position) {
+            path.pop_front();
+        }
+
+        let Some(&next) = path.front() else {
+            keeper.state = KeeperState::Working;
+            keeper.failed_plans = 0;
+            continue;
+        };
+
+        match Facing::towards(*position, next) {
+            Some(direction) if !collision_map.is_blocked(next) && !occupied.contains(&next) => {
+                intent.0 = Some(direction)
+            }
+            // The world changed under the path (or we got pushed off it); re-plan after a
+            // moment, and give up if it keeps happening.
+            _ => {
+                keeper.failed_plans += 1;
+                keeper.state = if keeper.failed_plans >= MAX_FAILED_PLANS {
+                    warn!(
+                        "{} keeps getting blocked on the way to {:?}",
+                        name, entry.destination
+                    );
+                    KeeperState::Stuck
+                } else {
+                    KeeperState::Blocked {
+                        retry: Timer::from_seconds(REPLAN_DELAY_SECONDS, TimerMode::Once),
+                    }
+                };
+            }
+        }
+    }
+}
@@ -1,9 +1,37 @@
+mod achievements;
+mod animals;
+mod buildings;
 mod camera;
+mod clock;
+mod collision;
 mod components;
+mod construction;
+mod data;
+#[cfg(debug_assertions)]
+mod debug;
+mod dialogue;
+mod economy;
 mod entrance;
+mod grid;
+mod interaction;
+mod keepers;
 mod map;
+mod movement;
+mod notifications;
+mod pathfinding;
 mod player;
+mod quests;
+mod replay;
+mod save;
+mod settings;
+mod sprite_animation;
+mod streaming;
+#[cfg(test)]
+mod testing;
 mod ui;
+mod upgrades;
+mod weather;
+mod ysort;
 
 use bevy::prelude::*;
 use bevy_tweening::TweeningPlugin;
@@ -31,12 +59,44 @@ fn main() {
         .join("assets")
         .join("tiled_types.json");
 
-    App::new()
-        .add_plugins(DefaultPlugins)
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins)
         .register_type::<components::BuildingEntrance>()
+        .register_type::<components::BuildingId>()
+        .register_type::<components::Interactable>()
         .add_plugins(TweeningPlugin)
-        .add_plugins(MapPlugin::new(tiled_types_path))
-        .add_plugins(player::PlayerPlugin)
+        .add_plugins((MapPlugin::new(tiled_types_path), streaming::StreamingPlugin))
+        .add_plugins((player::PlayerPlugin, sprite_animation::SpriteAnimationPlugin))
         .add_plugins((entrance::EntrancePlugin, camera::CameraPlugin, ui::UiPlugin))
-        .run();
+        .add_plugins((notifications::NotificationPlugin, buildings::BuildingPlugin))
+        .add_plugins(interaction::InteractionPlugin)
+        .add_plugins(ysort::YSortPlugin)
+        .add_plugins((
+            clock::ClockPlugin,
+            collision::CollisionPlugin,
+            movement::MovementPlugin,
+            keepers::KeeperPlugin,
+            animals::AnimalPlugin,
+        ))
+        .add_plugins((
+            weather::WeatherPlugin,
+            save::SavePlugin,
+            settings::SettingsPlugin,
+        ))
+        .add_plugins((
+            economy::EconomyPlugin,
+            upgrades::UpgradePlugin,
+            construction::ConstructionPlugin,
+        ))
+        .add_plugins((
+            achievements::AchievementPlugin,
+            quests::QuestPlugin,
+            dialogue::DialoguePlugin,
+        ));
+    #[cfg(debug_assertions)]
+    app.add_plugins(debug::DebugPlugin);
+    if let Some(replay) = replay::ReplayPlugin::from_args() {
+        app.add_plugins(replay);
+    }
+    app.run();
 }
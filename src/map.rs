@@ -1,57 +1,566 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_ecs_tiled::prelude::{regex::RegexSet, *};
+use serde::Deserialize;
 
-use crate::components::BuildingEntrance;
+use crate::{
+    buildings::BuildingRegistry,
+    clock::GameClock,
+    collision::CollisionMap,
+    components::{
+        ActiveMap, BuildingEntrance, BuildingId, Doorway, Facing, InEntrance, Interactable, Player,
+        PreviousTilePosition, TileGroup, TilePosition, TileRect,
+    },
+    data::JsonAssetAppExt,
+    entrance::{trigger_exit, zone_tile_group},
+    grid::{HexagonalGrid, IsometricGrid, OrthogonalGrid, TileGrid},
+    interaction::Interacted,
+};
 
+const DEFAULT_WORLD_FILE: &str = "data/sanctuary.world.json";
+
+/// Spawns the maps of the world and moves the player between them.
+///
+/// The world manifest lists the maps and the links between them. Only the map the player
+/// is on is spawned; the maps it links to are kept loaded so travelling doesn't hitch.
 pub struct MapPlugin {
-    tiled_types_path: PathBuf,
+    /// Where the reflected component types are exported for the Tiled editor, if anywhere.
+    tiled_types_path: Option<PathBuf>,
+    /// A `*.world.json` manifest, or a single `.tmx` file making up the whole world.
+    world_file: String,
 }
 
 impl MapPlugin {
     pub fn new(path: PathBuf) -> Self {
         Self {
-            tiled_types_path: path,
+            tiled_types_path: Some(path),
+            world_file: DEFAULT_WORLD_FILE.to_string(),
+        }
+    }
+
+    /// Loads `world_file` without exporting Tiled types, so test apps registering only a
+    /// few components don't overwrite the project's type file.
+    #[cfg(test)]
+    pub fn headless(world_file: &str) -> Self {
+        Self {
+            tiled_types_path: None,
+            world_file: world_file.to_string(),
         }
     }
 }
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(TiledPlugin(TiledPluginConfig {
-            tiled_types_export_file: Some(self.tiled_types_path.clone()),
-            // Filter out internal Bevy components to keep the Tiled export clean
-            // tiled_types_filter: TiledFilter::Names(vec![
-            //     "alveus_idle::components::BuildingEntrance".into(),
-            // ]),
-            tiled_types_filter: TiledFilter::from(
-                RegexSet::new([r"^alveus_idle::components::.*"]).unwrap(),
-            ),
-        }))
-        .add_systems(Startup, spawn_map)
-        .add_observer(on_map_created);
-    }
-}
-
-fn spawn_map(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn((
-        TiledMap(asset_server.load("map.tmx")),
+        app.insert_resource(WorldFile(self.world_file.clone()))
+            .init_resource::<LoadedMaps>()
+            .register_type::<Doorway>()
+            .init_json_asset::<WorldManifest>(&["world.json"])
+            .add_plugins(TiledPlugin(TiledPluginConfig {
+                tiled_types_export_file: self.tiled_types_path.clone(),
+                // Filter out internal Bevy components to keep the Tiled export clean
+                // tiled_types_filter: TiledFilter::Names(vec![
+                //     "alveus_idle::components::BuildingEntrance".into(),
+                // ]),
+                tiled_types_filter: TiledFilter::from(
+                    RegexSet::new([r"^alveus_idle::components::.*"]).unwrap(),
+                ),
+            }))
+            .add_systems(Startup, load_world)
+            .add_systems(
+                Update,
+                (
+                    build_world_graph,
+                    (spawn_start_map, reload_modified_maps, snap_doorways)
+                        .run_if(resource_exists::<WorldGraph>),
+                )
+                    .chain(),
+            )
+            .add_observer(on_map_created)
+            .add_observer(travel)
+            .add_observer(follow_links);
+    }
+}
+
+/// The world manifest or `.tmx` file loaded at startup, relative to the assets folder.
+#[derive(Resource)]
+struct WorldFile(String);
+
+/// The maps making up the world and how they connect, loaded from a `*.world.json` file.
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct WorldManifest {
+    /// The map a new game starts on. Keepers and placed buildings live there too.
+    pub start: String,
+    pub maps: HashMap<String, MapDefinition>,
+    #[serde(default)]
+    pub links: Vec<MapLink>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MapDefinition {
+    pub name: String,
+    pub file: String,
+    /// Where the player arrives the first time, unless the link says otherwise.
+    #[serde(default)]
+    pub spawn: [u32; 2],
+}
+
+/// A one-way connection from a zone on one map to another map.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MapLink {
+    pub from: String,
+    /// The building id of an entrance or the id of a [`Doorway`] on the `from` map.
+    pub via: String,
+    pub to: String,
+    /// Where the player arrives. Defaults to where they last left `to`, or its spawn.
+    #[serde(default)]
+    pub arrive: Option<[u32; 2]>,
+}
+
+impl WorldManifest {
+    /// A world made of a single map.
+    fn single(file: &str) -> Self {
+        let definition = MapDefinition {
+            name: file.to_string(),
+            file: file.to_string(),
+            spawn: [0, 0],
+        };
+        Self {
+            start: file.to_string(),
+            maps: HashMap::from([(file.to_string(), definition)]),
+            links: Vec::new(),
+        }
+    }
+
+    /// The link leaving `from` through the entrance or doorway `via`, if any.
+    pub fn link(&self, from: &str, via: &str) -> Option<&MapLink> {
+        self.links
+            .iter()
+            .find(|link| link.from == from && link.via == via)
+    }
+
+    /// Maps reachable in one step from `map`.
+    fn neighbors<'a>(&'a self, map: &'a str) -> impl Iterator<Item = &'a str> {
+        self.links
+            .iter()
+            .filter(move |link| link.from == map && link.to != map)
+            .map(|link| link.to.as_str())
+    }
+
+    /// A map integrity error if the start map or a link names an unknown map.
+    fn validate(&self, file: &str) -> Result<(), String> {
+        let referenced = std::iter::once(&self.start)
+            .chain(self.links.iter().flat_map(|link| [&link.from, &link.to]));
+        for map in referenced {
+            if !self.maps.contains_key(map) {
+                return Err(format!(
+                    "\n❌ MAP INTEGRITY ERROR ❌\nWorld: '{}'\nIssue: Unknown map '{}'. Known maps: {:?}\n",
+                    file,
+                    map,
+                    self.maps.keys().collect::<Vec<_>>()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The validated world manifest.
+///
+/// Only inserted once the manifest has loaded, so systems can use
+/// `resource_exists::<WorldGraph>` to wait for it.
+#[derive(Resource, Debug, Deref)]
+pub struct WorldGraph(WorldManifest);
+
+#[derive(Resource)]
+struct WorldManifestHandle(Handle<WorldManifest>);
+
+/// Identifies a spawned map by its key in the world manifest.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct MapId(pub String);
+
+/// Marks the world's start map while it is spawned. Placed buildings and animals are
+/// spawned under it, so they leave and come back with it; keepers are disabled meanwhile.
+#[derive(Component, Debug, Default)]
+pub struct HomeMap;
+
+/// Moves the player to the map `to`, despawning the current one.
+#[derive(Event, Debug, Clone)]
+pub struct Travel {
+    pub to: String,
+    /// Where the player arrives. Defaults to where they last left `to`, or its spawn.
+    pub arrive: Option<TilePosition>,
+    /// The building whose entrance the player used, when `to` is its interior.
+    pub building: Option<String>,
+}
+
+/// Triggered when the player arrives inside a building through its entrance.
+#[derive(Event, Debug, Clone)]
+pub struct EnteredInterior {
+    pub building: String,
+    pub map: String,
+}
+
+/// Where the player left each map, and the maps kept loaded for the next trip.
+#[derive(Resource, Default)]
+pub struct LoadedMaps {
+    return_positions: HashMap<String, TilePosition>,
+    preloaded: HashMap<String, Handle<TiledMapAsset>>,
+}
+
+fn load_world(mut commands: Commands, asset_server: Res<AssetServer>, file: Res<WorldFile>) {
+    if file.0.ends_with(".tmx") {
+        commands.insert_resource(WorldGraph(WorldManifest::single(&file.0)));
+    } else {
+        commands.insert_resource(WorldManifestHandle(asset_server.load(&file.0)));
+    }
+}
+
+/// (Re)builds the world graph whenever the manifest finishes loading or changes.
+fn build_world_graph(
+    mut commands: Commands,
+    mut asset_events: MessageReader<AssetEvent<WorldManifest>>,
+    handle: Option<Res<WorldManifestHandle>>,
+    manifests: Res<Assets<WorldManifest>>,
+    file: Res<WorldFile>,
+    graph: Option<Res<WorldGraph>>,
+) {
+    let changed = asset_events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. }
+        )
+    });
+    if !changed {
+        return;
+    }
+    let Some(manifest) = handle.and_then(|handle| manifests.get(&handle.0)) else {
+        return;
+    };
+    if let Err(issue) = manifest.validate(&file.0) {
+        // Keep playing on the previous graph when a reload breaks it.
+        if graph.is_some() {
+            error!(
+                "Reloaded world {} is invalid, keeping the previous one:{}",
+                file.0, issue
+            );
+            return;
+        }
+        panic!("{}", issue);
+    }
+    info!("Loaded world {} with {} maps", file.0, manifest.maps.len());
+    commands.insert_resource(WorldGraph(manifest.clone()));
+}
+
+fn spawn_start_map(mut commands: Commands, graph: Res<WorldGraph>, mut spawned: Local<bool>) {
+    if *spawned {
+        return;
+    }
+    *spawned = true;
+    commands.trigger(Travel {
+        to: graph.start.clone(),
+        arrive: None,
+        building: None,
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn travel(
+    trigger: On<Travel>,
+    mut commands: Commands,
+    graph: Res<WorldGraph>,
+    asset_server: Res<AssetServer>,
+    collision_map: Res<CollisionMap>,
+    mut maps: ResMut<LoadedMaps>,
+    active: Query<(Entity, &MapId), With<ActiveMap>>,
+    buildings: Query<&BuildingId>,
+    mut player: Query<
+        (
+            Entity,
+            &mut TilePosition,
+            &mut PreviousTilePosition,
+            &Facing,
+            Option<&InEntrance>,
+        ),
+        With<Player>,
+    >,
+) {
+    let to = &trigger.event().to;
+    let Some(definition) = graph.maps.get(to) else {
+        warn!("Cannot travel to unknown map: {}", to);
+        return;
+    };
+
+    // Coming back puts the player a step out of the entrance they leave through, so they
+    // don't walk straight back in.
+    let return_position = player
+        .single()
+        .ok()
+        .map(|(_, position, _, facing, in_entrance)| {
+            in_entrance
+                .and_then(|_| facing.opposite().step(*position))
+                .filter(|tile| !collision_map.is_blocked(*tile))
+                .unwrap_or(*position)
+        });
+
+    // The entrance the player stands in goes away with the map; step out of it first.
+    if let Ok((actor, _, _, _, Some(in_entrance))) = player.single() {
+        commands.entity(actor).remove::<InEntrance>();
+        if let Ok(building) = buildings.get(in_entrance.0) {
+            trigger_exit(&mut commands, actor, in_entrance.0, building);
+        }
+    }
+
+    for (entity, id) in active.iter() {
+        if let Some(position) = return_position {
+            maps.return_positions.insert(id.0.clone(), position);
+        }
+        commands.entity(entity).despawn();
+    }
+
+    info!("Travelling to {}", definition.name);
+    let handle = maps
+        .preloaded
+        .remove(to)
+        .unwrap_or_else(|| asset_server.load(&definition.file));
+    spawn_map(&mut commands, &graph, to, handle);
+
+    if let Ok((_, mut position, mut previous, _, _)) = player.single_mut() {
+        let [x, y] = definition.spawn;
+        *position = trigger
+            .event()
+            .arrive
+            .or_else(|| maps.return_positions.get(to).copied())
+            .unwrap_or(TilePosition { x, y });
+        // Don't slide across the world from the old map.
+        *previous = PreviousTilePosition(None);
+    }
+
+    if let Some(building) = &trigger.event().building {
+        commands.trigger(EnteredInterior {
+            building: building.clone(),
+            map: to.clone(),
+        });
+    }
+
+    let mut preloaded = std::mem::take(&mut maps.preloaded);
+    maps.preloaded = graph
+        .neighbors(to)
+        .filter_map(|neighbor| {
+            let handle = preloaded
+                .remove(neighbor)
+                .or_else(|| Some(asset_server.load(&graph.maps.get(neighbor)?.file)))?;
+            Some((neighbor.to_string(), handle))
+        })
+        .collect();
+}
+
+/// Spawns the map `id` of the world graph as the active map.
+fn spawn_map(commands: &mut Commands, graph: &WorldGraph, id: &str, handle: Handle<TiledMapAsset>) {
+    let Some(definition) = graph.maps.get(id) else {
+        return;
+    };
+    let mut map = commands.spawn((
+        Name::new(definition.name.clone()),
+        MapId(id.to_string()),
+        TiledMap(handle),
         TilemapAnchor::BottomLeft,
+        ActiveMap,
     ));
+    if id == graph.start {
+        map.insert(HomeMap);
+    }
+}
+
+/// Respawns the active map when its file changes on disk, keeping the player on their
+/// tile. Its zones are snapped again like on any arrival.
+///
+/// The player doesn't leave the map, so no entrance is exited: the one they stand in
+/// is forgotten quietly along with the old zones.
+fn reload_modified_maps(
+    mut commands: Commands,
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    graph: Option<Res<WorldGraph>>,
+    active: Query<(Entity, &MapId, &TiledMap), With<ActiveMap>>,
+    player: Query<Entity, (With<Player>, With<InEntrance>)>,
+) {
+    let Some(graph) = graph else {
+        return;
+    };
+    for event in asset_events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        let Ok((entity, map, tiled_map)) = active.single() else {
+            continue;
+        };
+        if tiled_map.0.id() != *id {
+            continue;
+        }
+
+        info!("Map {} changed on disk, respawning it", map.0);
+        if let Ok(player) = player.single() {
+            commands.entity(player).remove::<InEntrance>();
+        }
+        commands.entity(entity).despawn();
+        spawn_map(&mut commands, &graph, &map.0, tiled_map.0.clone());
+    }
+}
+
+/// Travels along the world link of an entrance or doorway the player interacts with.
+/// Buildings are only entered while they are open.
+fn follow_links(
+    trigger: On<Interacted>,
+    mut commands: Commands,
+    graph: Option<Res<WorldGraph>>,
+    registry: Option<Res<BuildingRegistry>>,
+    clock: Res<GameClock>,
+    zones: Query<(Option<&BuildingId>, Option<&Doorway>)>,
+    active: Query<&MapId, With<ActiveMap>>,
+) {
+    let (Some(graph), Ok(map)) = (graph, active.single()) else {
+        return;
+    };
+    let Ok((building, doorway)) = zones.get(trigger.event().target) else {
+        return;
+    };
+    let via = doorway
+        .map(|doorway| doorway.id.as_str())
+        .or(building.map(|building| building.id.as_str()));
+    let Some(link) = via.and_then(|via| graph.link(&map.0, via)) else {
+        return;
+    };
+    let closed = doorway.is_none()
+        && building
+            .and_then(|building| registry.as_ref()?.get(&building.id))
+            .is_some_and(|definition| !definition.opening_hours.is_open(clock.hour()));
+    if closed {
+        info!("{} is closed", link.via);
+        return;
+    }
+
+    commands.trigger(Travel {
+        to: link.to.clone(),
+        arrive: link.arrive.map(|[x, y]| TilePosition { x, y }),
+        building: doorway.is_none().then(|| link.via.clone()),
+    });
+}
+
+/// Snaps doorways to the grid and prompts with where they lead.
+fn snap_doorways(
+    mut commands: Commands,
+    graph: Res<WorldGraph>,
+    grids: MapGrids,
+    active: Query<&MapId, With<ActiveMap>>,
+    query: Query<
+        (
+            Entity,
+            &Doorway,
+            Option<&Transform>,
+            Option<&TiledObject>,
+            Option<&TileRect>,
+        ),
+        (Or<(With<TiledObject>, With<TileRect>)>, Without<TileGroup>),
+    >,
+) {
+    let Ok(map) = active.single() else {
+        return;
+    };
+    for (entity, doorway, transform, tiled_object, rect) in query.iter() {
+        let label = format!("Doorway {}", doorway.id);
+        let Some(destination) = graph
+            .link(&map.0, &doorway.id)
+            .and_then(|link| graph.maps.get(&link.to))
+        else {
+            panic!(
+                "\n❌ MAP INTEGRITY ERROR ❌\nObject: '{}'\nIssue: No link from map '{}' in the world manifest.\n",
+                label, map.0
+            );
+        };
+        let grid = grids.of(entity);
+        let Some(tile_group) = zone_tile_group(&label, grid, transform, tiled_object, rect) else {
+            continue;
+        };
+
+        commands
+            .entity(entity)
+            .insert(tile_group)
+            .insert_if_new(Interactable {
+                prompt: format!("go to {}", destination.name),
+            });
+    }
+}
+
+/// The [`TileGrid`] of each loaded map, for grid math on the active map or on the objects
+/// of a given map.
+#[derive(SystemParam)]
+pub struct MapGrids<'w, 's> {
+    parents: Query<'w, 's, &'static ChildOf>,
+    grids: Query<'w, 's, &'static TileGrid>,
+    active: Query<'w, 's, &'static TileGrid, With<ActiveMap>>,
+}
+
+impl MapGrids<'_, '_> {
+    /// The grid of the map the player is on, once it has loaded.
+    pub fn active(&self) -> Option<&TileGrid> {
+        self.active.iter().next()
+    }
+
+    /// The grid of the map `entity` was spawned under, once it has loaded.
+    pub fn of(&self, entity: Entity) -> Option<&TileGrid> {
+        self.parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| self.grids.get(ancestor).ok())
+    }
+}
+
+/// The grid geometry declared by a map file.
+fn map_grid(map: &tiled::Map) -> TileGrid {
+    let tile_size = UVec2::new(map.tile_width, map.tile_height).as_vec2();
+    match map.orientation {
+        tiled::Orientation::Orthogonal => TileGrid::new(OrthogonalGrid { tile_size }),
+        tiled::Orientation::Isometric => TileGrid::new(IsometricGrid {
+            tile_size,
+            height: map.height,
+        }),
+        tiled::Orientation::Hexagonal => {
+            // Tiled counts rows from the top, tile positions from the bottom.
+            let stagger_odd = map.stagger_index == tiled::StaggerIndex::Odd;
+            let stagger_columns = map.stagger_axis == tiled::StaggerAxis::X;
+            let flipped = !stagger_columns && map.height % 2 == 0;
+            TileGrid::new(HexagonalGrid {
+                tile_size,
+                side_length: map.hex_side_length.unwrap_or(0) as f32,
+                stagger_columns,
+                shift_odd: stagger_odd != flipped,
+            })
+        }
+        orientation => panic!(
+            "\n❌ MAP INTEGRITY ERROR ❌\nMap orientation: {:?}\nIssue: Unsupported orientation, use orthogonal, isometric or hexagonal.\n",
+            orientation
+        ),
+    }
 }
 
 /// Post-process map initialization (finding specific tiles, etc).
 fn on_map_created(
     trigger: On<TiledEvent<MapCreated>>,
-    map_query: Query<&TiledMapStorage, With<TiledMap>>,
+    mut commands: Commands,
+    map_query: Query<(&TiledMap, &TiledMapStorage)>,
+    map_assets: Res<Assets<TiledMapAsset>>,
     tiles_query: Query<(&TilePos, Option<&BuildingEntrance>)>,
 ) {
     let map_entity = trigger.event().origin;
-    let Ok(map_storage) = map_query.get(map_entity) else {
+    let Ok((tiled_map, map_storage)) = map_query.get(map_entity) else {
         return;
     };
 
+    if let Some(asset) = map_assets.get(&tiled_map.0) {
+        let grid = map_grid(&asset.map);
+        info!("Map {:?} uses {:?}", map_entity, grid);
+        commands.entity(map_entity).insert(grid);
+    }
+
     for (_, entities_list) in map_storage.tiles() {
         for &tile_entity in entities_list {
             let Ok((pos, building_entrance)) = tiles_query.get(tile_entity) else {
@@ -64,3 +573,143 @@ fn on_map_created(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entrance::{EnteredBuildingEvent, ExitedBuildingEvent},
+        interaction::InteractionPlugin,
+        testing::TestApp,
+    };
+
+    const TEST_WORLD: &str = "test/test.world.json";
+
+    fn active_map(app: &mut TestApp) -> Option<String> {
+        let world = app.world();
+        world
+            .query_filtered::<&MapId, With<ActiveMap>>()
+            .iter(world)
+            .next()
+            .map(|id| id.0.clone())
+    }
+
+    fn interact(app: &mut TestApp) {
+        app.press(KeyCode::KeyE);
+        app.step(1);
+        app.release(KeyCode::KeyE);
+        app.step(1);
+    }
+
+    #[test]
+    fn entrances_and_doorways_travel_between_maps() {
+        let mut app = TestApp::with_plugins(TEST_WORLD, InteractionPlugin);
+        app.world().init_resource::<GameClock>();
+        app.record::<ExitedBuildingEvent>();
+        app.wait_for_zones(1);
+        assert_eq!(active_map(&mut app).as_deref(), Some("outside"));
+        let loaded = app.world().resource::<LoadedMaps>();
+        assert!(loaded.preloaded.contains_key("inside"));
+
+        app.walk(KeyCode::KeyD, 3);
+        app.walk(KeyCode::KeyW, 3);
+        interact(&mut app);
+
+        assert_eq!(active_map(&mut app).as_deref(), Some("inside"));
+        assert_eq!(app.player_tile(), TilePosition { x: 2, y: 1 });
+        assert_eq!(app.count::<ExitedBuildingEvent>(), 1);
+        let player = app.player();
+        assert!(app.world().get::<InEntrance>(player).is_none());
+        app.wait_until("doorway", |world| {
+            world
+                .query_filtered::<(), (With<Doorway>, With<TileGroup>)>()
+                .iter(world)
+                .count()
+                == 1
+        });
+        let world = app.world();
+        assert_eq!(world.query::<&BuildingEntrance>().iter(world).count(), 0);
+
+        app.walk(KeyCode::KeyS, 1);
+        interact(&mut app);
+
+        assert_eq!(active_map(&mut app).as_deref(), Some("outside"));
+        assert_eq!(app.player_tile(), TilePosition { x: 3, y: 2 });
+    }
+
+    #[test]
+    fn returning_from_a_building_does_not_enter_it_again() {
+        let mut app = TestApp::with_plugins(TEST_WORLD, InteractionPlugin);
+        app.world().init_resource::<GameClock>();
+        app.record::<EnteredBuildingEvent>();
+        app.wait_for_zones(1);
+
+        app.walk(KeyCode::KeyD, 3);
+        app.walk(KeyCode::KeyW, 3);
+        interact(&mut app);
+        assert_eq!(app.count::<EnteredBuildingEvent>(), 1);
+
+        app.wait_until("doorway", |world| {
+            world
+                .query_filtered::<(), (With<Doorway>, With<TileGroup>)>()
+                .iter(world)
+                .count()
+                == 1
+        });
+        app.walk(KeyCode::KeyS, 1);
+        interact(&mut app);
+        app.wait_for_zones(1);
+        app.step(5);
+
+        assert_eq!(active_map(&mut app).as_deref(), Some("outside"));
+        assert_eq!(app.count::<EnteredBuildingEvent>(), 1);
+        let player = app.player();
+        assert!(app.world().get::<InEntrance>(player).is_none());
+    }
+
+    #[test]
+    fn closed_buildings_are_not_entered() {
+        let mut app = TestApp::with_plugins(TEST_WORLD, InteractionPlugin);
+        let mut clock = GameClock::default();
+        clock.restore(22.0 * 60.0);
+        app.world().insert_resource(clock);
+        app.wait_for_zones(1);
+
+        app.walk(KeyCode::KeyD, 3);
+        app.walk(KeyCode::KeyW, 3);
+        interact(&mut app);
+
+        assert_eq!(active_map(&mut app).as_deref(), Some("outside"));
+        assert_eq!(app.player_tile(), TilePosition { x: 3, y: 3 });
+    }
+
+    #[test]
+    fn modified_map_respawns_without_moving_the_player() {
+        let mut app = TestApp::new("test/entrance_test.tmx");
+        app.record::<ExitedBuildingEvent>();
+        app.wait_for_zones(1);
+        app.walk(KeyCode::KeyD, 3);
+        app.walk(KeyCode::KeyW, 3);
+        let tile = app.player_tile();
+
+        let world = app.world();
+        let (old_map, id) = world
+            .query_filtered::<(Entity, &TiledMap), With<ActiveMap>>()
+            .single(world)
+            .map(|(entity, map)| (entity, map.0.id()))
+            .unwrap();
+        world.write_message(AssetEvent::Modified { id });
+        app.wait_until("map respawned", |world| {
+            world
+                .query_filtered::<Entity, With<ActiveMap>>()
+                .iter(world)
+                .any(|map| map != old_map)
+        });
+        app.wait_for_zones(1);
+
+        assert!(app.world().get_entity(old_map).is_err());
+        assert_eq!(app.player_tile(), tile);
+        // Staying on the map isn't leaving the building.
+        assert_eq!(app.count::<ExitedBuildingEvent>(), 0);
+    }
+}
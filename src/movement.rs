@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    collision::CollisionMap,
+    components::{
+        ActorIntentSet, Facing, MoveIntent, MovementCooldown, PlayerMovementSet,
+        PreviousTilePosition, TilePosition,
+    },
+    map::MapGrids,
+};
+
+/// Grid movement shared by the player and NPCs.
+///
+/// Actors express where they want to go through [`MoveIntent`] in [`ActorIntentSet`];
+/// stepping, cooldowns and collision are handled here in [`PlayerMovementSet`]. Both run
+/// in `FixedUpdate` so movement does not depend on the frame rate; transforms are
+/// interpolated between ticks in `Update`.
+pub struct MovementPlugin;
+
+impl Plugin for MovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(FixedUpdate, ActorIntentSet.before(PlayerMovementSet))
+            .add_systems(
+                FixedUpdate,
+                (remember_previous_tiles, step_actors)
+                    .chain()
+                    .in_set(PlayerMovementSet),
+            )
+            .add_systems(
+                Update,
+                interpolate_actor_transforms.in_set(PlayerMovementSet),
+            );
+    }
+}
+
+fn remember_previous_tiles(mut actors: Query<(&TilePosition, &mut PreviousTilePosition)>) {
+    for (position, mut previous) in actors.iter_mut() {
+        previous.set_if_neq(PreviousTilePosition(Some(*position)));
+    }
+}
+
+/// Applies each actor's [`MoveIntent`] once its cooldown has elapsed. Actors can't step
+/// onto a solid tile or a tile another actor stands on.
+fn step_actors(
+    mut actors: Query<(
+        &mut TilePosition,
+        &mut MovementCooldown,
+        &mut Facing,
+        &mut MoveIntent,
+    )>,
+    collision_map: Res<CollisionMap>,
+    time: Res<Time>,
+) {
+    let mut occupied: HashSet<TilePosition> =
+        actors.iter().map(|(position, ..)| *position).collect();
+    for (mut tile_position, mut cooldown, mut facing, mut intent) in actors.iter_mut() {
+        cooldown.0.tick(time.delta());
+
+        if !cooldown.0.is_finished() {
+            continue;
+        }
+        let Some(direction) = intent.0.take() else {
+            continue;
+        };
+
+        facing.set_if_neq(direction);
+
+        let Some(next) = direction.step(*tile_position) else {
+            continue;
+        };
+        if collision_map.is_blocked(next) || occupied.contains(&next) {
+            continue;
+        }
+
+        occupied.remove(&*tile_position);
+        occupied.insert(next);
+        *tile_position = next;
+        cooldown.0.reset();
+    }
+}
+
+/// Places each actor's Transform (pixel position) between its previous and current
+/// TilePosition (grid position) on the active map, by how far the clock is into the next
+/// tick.
+fn interpolate_actor_transforms(
+    mut query: Query<(&TilePosition, &PreviousTilePosition, &mut Transform)>,
+    grids: MapGrids,
+    time: Res<Time<Fixed>>,
+) {
+    let Some(grid) = grids.active() else {
+        return;
+    };
+    let progress = time.overstep_fraction();
+    for (position, previous, mut transform) in query.iter_mut() {
+        let to = grid.tile_center(*position);
+        let from = previous.0.map_or(to, |previous| grid.tile_center(previous));
+        let translation = from.lerp(to, progress);
+
+        if transform.translation.truncate() != translation {
+            transform.translation.x = translation.x;
+            transform.translation.y = translation.y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::{
+        components::{Solid, TileRect},
+        entrance::SpawnZoneExt,
+        testing::{FRAME_TIME, TestApp},
+    };
+
+    const TEST_MAP: &str = "test/entrance_test.tmx";
+
+    #[test]
+    fn held_key_steps_once_per_cooldown() {
+        let mut app = TestApp::new(TEST_MAP);
+        let player = app.player();
+        let cooldown = app
+            .world()
+            .get::<MovementCooldown>(player)
+            .unwrap()
+            .0
+            .duration();
+        let frames_per_step = (cooldown.as_secs_f32() / FRAME_TIME.as_secs_f32()).ceil() as usize;
+
+        app.walk(KeyCode::KeyD, 1);
+        app.press(KeyCode::KeyD);
+        app.step(frames_per_step * 3);
+        app.release(KeyCode::KeyD);
+
+        let tile = app.player_tile();
+        assert_eq!(tile.y, 0);
+        assert!((3..=5).contains(&tile.x), "walked to {:?}", tile);
+        assert_eq!(*app.world().get::<Facing>(player).unwrap(), Facing::Right);
+    }
+
+    #[test]
+    fn solid_zone_blocks_the_player_but_turns_them() {
+        let mut app = TestApp::new(TEST_MAP);
+        let world = app.world();
+        world
+            .commands()
+            .spawn_zone(TileRect::new(TilePosition { x: 0, y: 1 }, 2, 1), Solid);
+        world.flush();
+        app.wait_for_zones(2);
+
+        app.press(KeyCode::KeyW);
+        app.step(20);
+        app.release(KeyCode::KeyW);
+
+        let player = app.player();
+        assert_eq!(app.player_tile(), TilePosition { x: 0, y: 0 });
+        assert_eq!(*app.world().get::<Facing>(player).unwrap(), Facing::Up);
+    }
+
+    #[test]
+    fn actors_do_not_walk_through_each_other() {
+        let mut app = TestApp::new(TEST_MAP);
+        app.world().spawn((
+            TilePosition { x: 1, y: 0 },
+            MovementCooldown(Timer::from_seconds(0.3, TimerMode::Once)),
+            Facing::default(),
+            MoveIntent::default(),
+        ));
+
+        app.press(KeyCode::KeyD);
+        app.step(20);
+        app.release(KeyCode::KeyD);
+
+        assert_eq!(app.player_tile(), TilePosition { x: 0, y: 0 });
+    }
+
+    #[test]
+    fn actors_are_drawn_at_the_center_of_the_map_tiles() {
+        let mut app = TestApp::new("test/wide_tiles_test.tmx");
+        app.wait_for_zones(1);
+
+        app.walk(KeyCode::KeyD, 1);
+        app.step(5);
+
+        let player = app.player();
+        let transform = app.world().get::<Transform>(player).unwrap();
+        assert_eq!(transform.translation.truncate(), Vec2::new(72.0, 12.0));
+    }
+
+    /// Where the player ends up after holding right then up for a second each, rendering
+    /// `fps` frames per second.
+    fn walk_at_frame_rate(fps: u32) -> TilePosition {
+        let mut app = TestApp::new(TEST_MAP);
+        app.world()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                1.0 / fps as f64,
+            )));
+        let frames = fps as usize;
+
+        app.press(KeyCode::KeyD);
+        app.step(frames);
+        app.release(KeyCode::KeyD);
+        app.press(KeyCode::KeyW);
+        app.step(frames);
+        app.release(KeyCode::KeyW);
+        app.step(frames);
+        app.player_tile()
+    }
+
+    #[test]
+    fn movement_does_not_depend_on_frame_rate() {
+        let at_30 = walk_at_frame_rate(30);
+        let at_240 = walk_at_frame_rate(240);
+
+        assert!(at_30.x > 0 && at_30.y > 0, "walked to {:?}", at_30);
+        assert_eq!(at_30, at_240);
+    }
+}
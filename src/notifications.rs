@@ -0,0 +1,608 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_tweening::{lens::UiPositionLens, *};
+use serde::Deserialize;
+
+const DEFAULT_TOAST_DURATION: Duration = Duration::from_secs(4);
+
+pub struct NotificationPlugin;
+
+impl Plugin for NotificationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Notifications>()
+            .init_resource::<NotificationConfig>()
+            .add_systems(
+                Update,
+                (
+                    dismiss_toasts_on_input,
+                    expire_toasts,
+                    apply_dismiss_requests,
+                    show_pending_notifications,
+                    restack_toasts,
+                )
+                    .chain(),
+            )
+            .add_observer(despawn_toast_on_completion);
+    }
+}
+
+/// Toast layout, overridable from the settings file. Sizes and offsets are in UI pixels.
+#[derive(Resource, Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationConfig {
+    pub width: f32,
+    pub height: f32,
+    /// Distance from the left and bottom edges of the screen.
+    pub margin: f32,
+    /// Gap between stacked toasts.
+    pub spacing: f32,
+    /// Bottom offset of a toast that is fully off-screen.
+    pub hidden_bottom: f32,
+    pub max_visible: usize,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            width: 300.0,
+            height: 120.0,
+            margin: 12.0,
+            spacing: 8.0,
+            hidden_bottom: -150.0,
+            max_visible: 3,
+        }
+    }
+}
+
+impl NotificationConfig {
+    fn slot_bottom(&self, slot: usize) -> f32 {
+        self.margin + slot as f32 * (self.height + self.spacing)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NotificationPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// What happens when a notification arrives while another one of the same category is shown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CategoryMode {
+    /// Show it as an additional toast.
+    #[default]
+    Stack,
+    /// Swap the content of the existing toast and restart its timeout.
+    Replace,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub icon: Option<Handle<Image>>,
+    pub priority: NotificationPriority,
+    pub category: Option<String>,
+    pub mode: CategoryMode,
+    /// `None` keeps the toast on screen until it is dismissed.
+    pub duration: Option<Duration>,
+}
+
+impl Notification {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            body: String::new(),
+            icon: None,
+            priority: NotificationPriority::default(),
+            category: None,
+            mode: CategoryMode::default(),
+            duration: Some(DEFAULT_TOAST_DURATION),
+        }
+    }
+
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn with_icon(mut self, icon: Handle<Image>) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: NotificationPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_category(mut self, category: impl Into<String>, mode: CategoryMode) -> Self {
+        self.category = Some(category.into());
+        self.mode = mode;
+        self
+    }
+
+    pub fn sticky(mut self) -> Self {
+        self.duration = None;
+        self
+    }
+}
+
+/// Notification service: any system can push toasts or dismiss them by category.
+#[derive(Resource, Default)]
+pub struct Notifications {
+    pending: Vec<Notification>,
+    dismiss_requests: Vec<String>,
+}
+
+impl Notifications {
+    pub fn push(&mut self, notification: Notification) {
+        // Keep the queue ordered by priority, FIFO within the same priority.
+        let index = self
+            .pending
+            .iter()
+            .position(|queued| queued.priority < notification.priority)
+            .unwrap_or(self.pending.len());
+        self.pending.insert(index, notification);
+    }
+
+    /// Drops queued notifications of `category` and slides out the visible ones.
+    pub fn dismiss_category(&mut self, category: impl Into<String>) {
+        let category = category.into();
+        self.pending
+            .retain(|queued| queued.category.as_deref() != Some(category.as_str()));
+        self.dismiss_requests.push(category);
+    }
+}
+
+#[derive(Component)]
+struct Toast {
+    category: Option<String>,
+    timeout: Option<Timer>,
+    /// Monotonic spawn order, used to keep stacked toasts in a stable order.
+    order: u64,
+    slot: usize,
+}
+
+#[derive(Component)]
+struct ToastDismissing;
+
+fn slide_tween(config: &NotificationConfig, node: &Node, bottom: f32, ease: EaseFunction) -> Tween {
+    Tween::new(
+        ease,
+        Duration::from_millis(500),
+        UiPositionLens {
+            start: UiRect {
+                left: node.left,
+                right: node.right,
+                top: node.top,
+                bottom: node.bottom,
+            },
+            end: UiRect {
+                bottom: px(bottom),
+                left: px(config.margin),
+                top: Val::Auto,
+                right: Val::Auto,
+            },
+        },
+    )
+}
+
+fn start_dismissal(
+    commands: &mut Commands,
+    config: &NotificationConfig,
+    entity: Entity,
+    node: &Node,
+) {
+    commands.entity(entity).insert((
+        TweenAnim::new(slide_tween(
+            config,
+            node,
+            config.hidden_bottom,
+            EaseFunction::CubicIn,
+        )),
+        ToastDismissing,
+    ));
+}
+
+fn spawn_toast_content(toast: &mut EntityCommands, notification: &Notification) {
+    let icon = notification.icon.clone();
+    let title = notification.title.clone();
+    let body = notification.body.clone();
+
+    toast.with_children(|builder| {
+        if let Some(icon) = icon {
+            builder.spawn((
+                ImageNode {
+                    image: icon,
+                    ..default()
+                },
+                Node {
+                    width: px(64),
+                    ..default()
+                },
+            ));
+        }
+        builder
+            .spawn(Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: px(4),
+                ..default()
+            })
+            .with_children(|column| {
+                column.spawn((
+                    Text::new(title),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                ));
+                if !body.is_empty() {
+                    column.spawn((
+                        Text::new(body),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                    ));
+                }
+            });
+    });
+}
+
+/// Dismisses a toast when it is tapped, or the newest one when Escape is pressed.
+fn dismiss_toasts_on_input(
+    mut commands: Commands,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    config: Res<NotificationConfig>,
+    tapped: Query<(Entity, &Interaction, &Node), (Changed<Interaction>, Without<ToastDismissing>)>,
+    toasts: Query<(Entity, &Toast, &Node), Without<ToastDismissing>>,
+) {
+    for (entity, interaction, node) in tapped.iter() {
+        if *interaction == Interaction::Pressed && toasts.contains(entity) {
+            start_dismissal(&mut commands, &config, entity, node);
+        }
+    }
+
+    if kb_input.just_pressed(KeyCode::Escape)
+        && let Some((entity, _, node)) = toasts.iter().max_by_key(|(_, toast, _)| toast.order)
+    {
+        start_dismissal(&mut commands, &config, entity, node);
+    }
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    config: Res<NotificationConfig>,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut Toast, &Node), Without<ToastDismissing>>,
+) {
+    for (entity, mut toast, node) in toasts.iter_mut() {
+        let Some(timeout) = toast.timeout.as_mut() else {
+            continue;
+        };
+        if timeout.tick(time.delta()).just_finished() {
+            start_dismissal(&mut commands, &config, entity, node);
+        }
+    }
+}
+
+fn apply_dismiss_requests(
+    mut commands: Commands,
+    config: Res<NotificationConfig>,
+    mut notifications: ResMut<Notifications>,
+    toasts: Query<(Entity, &Toast, &Node), Without<ToastDismissing>>,
+) {
+    for category in notifications.dismiss_requests.drain(..) {
+        for (entity, toast, node) in toasts.iter() {
+            if toast.category.as_deref() == Some(category.as_str()) {
+                start_dismissal(&mut commands, &config, entity, node);
+            }
+        }
+    }
+}
+
+fn show_pending_notifications(
+    mut commands: Commands,
+    config: Res<NotificationConfig>,
+    mut notifications: ResMut<Notifications>,
+    mut toasts: Query<(Entity, &mut Toast, &Node, Has<ToastDismissing>)>,
+    mut next_order: Local<u64>,
+) {
+    if notifications.pending.is_empty() {
+        return;
+    }
+
+    let mut visible = toasts
+        .iter()
+        .filter(|(_, _, _, dismissing)| !dismissing)
+        .count();
+    let mut still_pending = Vec::new();
+
+    for notification in std::mem::take(&mut notifications.pending) {
+        // A toast on its way out is only brought back if there is room for it; otherwise
+        // the notification waits for a free slot like any other.
+        if notification.mode == CategoryMode::Replace
+            && let Some(category) = notification.category.as_deref()
+            && let Some((entity, mut toast, node, dismissing)) =
+                toasts.iter_mut().find(|(_, toast, _, dismissing)| {
+                    toast.category.as_deref() == Some(category)
+                        && (!dismissing || visible < config.max_visible)
+                })
+        {
+            toast.timeout = notification
+                .duration
+                .map(|duration| Timer::new(duration, TimerMode::Once));
+
+            let mut toast_commands = commands.entity(entity);
+            toast_commands.despawn_children();
+            spawn_toast_content(&mut toast_commands, &notification);
+
+            // Bring a toast that was on its way out back on screen.
+            if dismissing {
+                toast_commands
+                    .remove::<ToastDismissing>()
+                    .insert(TweenAnim::new(slide_tween(
+                        &config,
+                        node,
+                        config.slot_bottom(toast.slot),
+                        EaseFunction::CubicOut,
+                    )));
+                visible += 1;
+            }
+            continue;
+        }
+
+        if visible >= config.max_visible {
+            still_pending.push(notification);
+            continue;
+        }
+
+        let slot = visible;
+        visible += 1;
+        *next_order += 1;
+
+        let hidden = Node {
+            position_type: PositionType::Absolute,
+            bottom: px(config.hidden_bottom),
+            left: px(config.margin),
+            width: px(config.width),
+            height: px(config.height),
+            padding: UiRect::all(px(8)),
+            column_gap: px(8),
+            align_items: AlignItems::Center,
+            ..default()
+        };
+        let tween = slide_tween(
+            &config,
+            &hidden,
+            config.slot_bottom(slot),
+            EaseFunction::CubicOut,
+        );
+
+        let mut toast_commands = commands.spawn((
+            hidden,
+            Button,
+            BackgroundColor(Color::srgba(0.1, 0.08, 0.06, 0.9)),
+            Toast {
+                category: notification.category.clone(),
+                timeout: notification
+                    .duration
+                    .map(|duration| Timer::new(duration, TimerMode::Once)),
+                order: *next_order,
+                slot,
+            },
+            TweenAnim::new(tween),
+        ));
+        spawn_toast_content(&mut toast_commands, &notification);
+    }
+
+    notifications.pending = still_pending;
+}
+
+/// Slides the remaining toasts down when one above or below them leaves.
+fn restack_toasts(
+    mut commands: Commands,
+    config: Res<NotificationConfig>,
+    mut toasts: Query<(Entity, &mut Toast, &Node), Without<ToastDismissing>>,
+) {
+    let mut ordered: Vec<_> = toasts.iter_mut().collect();
+    ordered.sort_by_key(|(_, toast, _)| toast.order);
+
+    for (slot, (entity, mut toast, node)) in ordered.into_iter().enumerate() {
+        if toast.slot == slot {
+            continue;
+        }
+        toast.slot = slot;
+        commands.entity(entity).insert(TweenAnim::new(slide_tween(
+            &config,
+            node,
+            config.slot_bottom(slot),
+            EaseFunction::CubicOut,
+        )));
+    }
+}
+
+fn despawn_toast_on_completion(
+    trigger: On<AnimCompletedEvent>,
+    mut commands: Commands,
+    query: Query<Entity, With<ToastDismissing>>,
+) {
+    if query.get(trigger.anim_entity).is_ok() {
+        commands.entity(trigger.anim_entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    const TEST_MAP: &str = "test/entrance_test.tmx";
+
+    fn app() -> TestApp {
+        TestApp::with_plugins(TEST_MAP, (TweeningPlugin, NotificationPlugin))
+    }
+
+    fn push(app: &mut TestApp, notification: Notification) {
+        app.world()
+            .resource_mut::<Notifications>()
+            .push(notification);
+    }
+
+    /// Titles of the toasts on screen, bottom first, skipping the ones sliding out.
+    fn shown(app: &mut TestApp) -> Vec<String> {
+        let world = app.world();
+        let mut toasts: Vec<_> = world
+            .query_filtered::<(Entity, &Toast), Without<ToastDismissing>>()
+            .iter(world)
+            .map(|(entity, toast)| (toast.order, entity))
+            .collect();
+        toasts.sort();
+        toasts
+            .into_iter()
+            .map(|(_, entity)| toast_title(world, entity))
+            .collect()
+    }
+
+    /// The first text under the toast's column, which is its title.
+    fn toast_title(world: &mut World, toast: Entity) -> String {
+        let columns: Vec<Entity> = world
+            .query::<(Entity, &ChildOf)>()
+            .iter(world)
+            .filter(|(_, parent)| parent.parent() == toast)
+            .map(|(entity, _)| entity)
+            .collect();
+        world
+            .query::<(&Text, &ChildOf)>()
+            .iter(world)
+            .find(|(_, parent)| columns.contains(&parent.parent()))
+            .map(|(text, _)| text.0.clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn queue_is_ordered_by_priority_then_arrival() {
+        let mut notifications = Notifications::default();
+        notifications.push(Notification::new("first"));
+        notifications.push(Notification::new("low").with_priority(NotificationPriority::Low));
+        notifications.push(Notification::new("urgent").with_priority(NotificationPriority::High));
+        notifications.push(Notification::new("second"));
+
+        let titles: Vec<_> = notifications
+            .pending
+            .iter()
+            .map(|queued| queued.title.as_str())
+            .collect();
+        assert_eq!(titles, ["urgent", "first", "second", "low"]);
+    }
+
+    #[test]
+    fn toasts_past_the_cap_wait_for_a_slot() {
+        let mut app = app();
+        for title in ["a", "b", "c", "d"] {
+            push(&mut app, Notification::new(title).sticky());
+        }
+        app.step(1);
+
+        assert_eq!(shown(&mut app), ["a", "b", "c"]);
+        assert_eq!(app.world().resource::<Notifications>().pending.len(), 1);
+    }
+
+    #[test]
+    fn replace_swaps_the_content_of_the_shown_toast() {
+        let mut app = app();
+        let toast = |title: &str| {
+            Notification::new(title)
+                .with_category("building", CategoryMode::Replace)
+                .sticky()
+        };
+        push(&mut app, toast("Otters"));
+        app.step(1);
+        push(&mut app, toast("Foxes"));
+        app.step(2);
+
+        assert_eq!(shown(&mut app), ["Foxes"]);
+    }
+
+    #[test]
+    fn replace_does_not_bring_a_toast_back_past_the_cap() {
+        let mut app = app();
+        app.world().resource_mut::<NotificationConfig>().max_visible = 1;
+        let building = |title: &str| {
+            Notification::new(title)
+                .with_category("building", CategoryMode::Replace)
+                .sticky()
+        };
+        push(&mut app, building("Otters"));
+        app.step(1);
+
+        app.world()
+            .resource_mut::<Notifications>()
+            .dismiss_category("building");
+        push(&mut app, Notification::new("Day 2").sticky());
+        push(&mut app, building("Foxes"));
+        app.step(1);
+
+        assert_eq!(shown(&mut app), ["Day 2"]);
+        assert_eq!(app.world().resource::<Notifications>().pending.len(), 1);
+    }
+
+    #[test]
+    fn expired_toasts_leave_and_the_rest_slide_down() {
+        let mut app = app();
+        let mut quick = Notification::new("quick");
+        quick.duration = Some(Duration::from_millis(100));
+        push(&mut app, quick);
+        push(&mut app, Notification::new("stays").sticky());
+        app.step(1);
+        assert_eq!(shown(&mut app), ["quick", "stays"]);
+
+        app.wait_until("expired toast despawned", |world| {
+            world.query::<&Toast>().iter(world).count() == 1
+        });
+        let world = app.world();
+        let toast = world.query::<&Toast>().single(world).unwrap();
+        assert_eq!(toast.slot, 0);
+    }
+
+    #[test]
+    fn dismissing_a_category_drops_queued_and_shown_toasts() {
+        let mut app = app();
+        app.world().resource_mut::<NotificationConfig>().max_visible = 1;
+        let building = |title: &str| {
+            Notification::new(title)
+                .with_category("building", CategoryMode::Stack)
+                .sticky()
+        };
+        push(&mut app, building("Otters"));
+        push(&mut app, building("Foxes"));
+        app.step(1);
+
+        app.world()
+            .resource_mut::<Notifications>()
+            .dismiss_category("building");
+        app.step(1);
+
+        assert!(shown(&mut app).is_empty());
+        assert!(app.world().resource::<Notifications>().pending.is_empty());
+    }
+
+    #[test]
+    fn escape_dismisses_the_newest_toast() {
+        let mut app = app();
+        push(&mut app, Notification::new("older").sticky());
+        app.step(1);
+        push(&mut app, Notification::new("newer").sticky());
+        app.step(1);
+
+        app.press(KeyCode::Escape);
+        app.step(1);
+        app.release(KeyCode::Escape);
+
+        assert_eq!(shown(&mut app), ["older"]);
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::components::{Facing, TilePosition};
+
+const DIRECTIONS: [Facing; 4] = [Facing::Up, Facing::Down, Facing::Left, Facing::Right];
+
+fn manhattan(a: TilePosition, b: TilePosition) -> u32 {
+    a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+}
+
+/// A* over the tile grid with 4-way movement.
+///
+/// Returns the steps from `start` (exclusive) to `goal` (inclusive), or `None` if the goal
+/// is unreachable within `max_expanded` visited tiles. The limit keeps searches bounded on
+/// infinite maps.
+pub fn find_path(
+    start: TilePosition,
+    goal: TilePosition,
+    is_blocked: impl Fn(TilePosition) -> bool,
+    max_expanded: usize,
+) -> Option<Vec<TilePosition>> {
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<TilePosition, TilePosition> = HashMap::new();
+    let mut cost: HashMap<TilePosition, u32> = HashMap::new();
+
+    cost.insert(start, 0);
+    open.push(Reverse((manhattan(start, goal), 0, start.x, start.y)));
+
+    let mut expanded = 0;
+    while let Some(Reverse((_, current_cost, x, y))) = open.pop() {
+        let current = TilePosition { x, y };
+        if current == goal {
+            let mut path = vec![current];
+            let mut step = current;
+            while let Some(&previous) = came_from.get(&step) {
+                if previous == start {
+                    break;
+                }
+                path.push(previous);
+                step = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        // Skip stale heap entries superseded by a cheaper route.
+        if cost.get(&current).is_some_and(|&best| best < current_cost) {
+            continue;
+        }
+
+        expanded += 1;
+        if expanded > max_expanded {
+            return None;
+        }
+
+        for direction in DIRECTIONS {
+            let Some(next) = direction.step(current) else {
+                continue;
+            };
+            if is_blocked(next) {
+                continue;
+            }
+
+            let next_cost = current_cost + 1;
+            if cost.get(&next).is_none_or(|&best| next_cost < best) {
+                cost.insert(next, next_cost);
+                came_from.insert(next, current);
+                open.push(Reverse((
+                    next_cost + manhattan(next, goal),
+                    next_cost,
+                    next.x,
+                    next.y,
+                )));
+            }
+        }
+    }
+
+    None
+}
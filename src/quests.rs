@@ -0,0 +1,435 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use rand::{SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::Counters,
+    buildings::BuildingRegistry,
+    clock::{GameClock, SimulationSeed, deserialize_time_of_day},
+    components::{BuildingId, Player},
+    data::JsonAssetAppExt,
+    economy::{Cost, Economy},
+    entrance::EnteredBuildingEvent,
+    interaction::Interacted,
+    notifications::{Notification, Notifications},
+    ui::{PanelSlot, panel_node},
+};
+
+const QUEST_DEFINITIONS_FILE: &str = "data/sanctuary.quests.json";
+const TOGGLE_PANEL_KEY: KeyCode = KeyCode::KeyQ;
+const QUESTS_PER_DAY: usize = 3;
+
+pub struct QuestPlugin;
+
+impl Plugin for QuestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_json_asset::<QuestDefinitions>(&["quests.json"])
+            .init_resource::<QuestBoard>()
+            .add_systems(Startup, (load_quest_definitions, spawn_quest_panel))
+            .add_systems(
+                Update,
+                (
+                    rebuild_quest_catalog,
+                    (rotate_daily_quests, expire_quests).run_if(resource_exists::<QuestCatalog>),
+                    toggle_quest_panel,
+                    refresh_quest_panel.run_if(resource_exists::<QuestCatalog>),
+                )
+                    .chain(),
+            )
+            .add_observer(visit_quest_giver)
+            .add_observer(record_visited_buildings);
+    }
+}
+
+/// Quest templates keyed by id, loaded from a `*.quests.json` file.
+#[derive(Asset, TypePath, Deserialize)]
+#[serde(transparent)]
+pub struct QuestDefinitions(pub HashMap<String, QuestDefinition>);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuestDefinition {
+    pub title: String,
+    pub description: String,
+    /// Building id whose entrance hands the quest out and takes it back in when the player
+    /// interacts with it.
+    pub giver: String,
+    pub objective: Objective,
+    /// Paid into the economy on completion.
+    pub reward: Cost,
+    /// Time of day the quest must be handed in by, as `"HH:MM"`.
+    #[serde(default, deserialize_with = "deserialize_deadline")]
+    pub deadline: Option<u32>,
+}
+
+fn deserialize_deadline<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    deserialize_time_of_day(deserializer).map(Some)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// The named [`Counters`] entry must grow by `amount` after the quest is accepted.
+    Counter { name: String, amount: u64 },
+    /// `amount` different buildings must be entered after the quest is accepted.
+    Visit { amount: u64 },
+    /// Resources handed over to the giver on hand-in.
+    Deliver(Cost),
+}
+
+/// Today's quests. Saved with the game.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct QuestBoard {
+    /// Day the board was rolled for; 0 before the first roll.
+    day: u32,
+    /// Quests waiting at their giver.
+    offered: Vec<String>,
+    active: Vec<ActiveQuest>,
+    completed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ActiveQuest {
+    id: String,
+    /// Counter value when the quest was accepted.
+    baseline: u64,
+    /// Building ids entered since the quest was accepted.
+    #[serde(default)]
+    visited: HashSet<String>,
+}
+
+impl ActiveQuest {
+    /// Progress towards the objective as `(current, target)`.
+    fn progress(
+        &self,
+        objective: &Objective,
+        counters: &Counters,
+        economy: &Economy,
+    ) -> (u64, u64) {
+        match objective {
+            Objective::Counter { name, amount } => (
+                counters
+                    .get(name)
+                    .saturating_sub(self.baseline)
+                    .min(*amount),
+                *amount,
+            ),
+            Objective::Visit { amount } => ((self.visited.len() as u64).min(*amount), *amount),
+            Objective::Deliver(cost) => {
+                let target = (cost.coins + cost.food) as u64;
+                let held = economy.coins().min(cost.coins) + economy.food().min(cost.food);
+                (held as u64, target)
+            }
+        }
+    }
+}
+
+/// Every quest template, available once the definition file has loaded.
+#[derive(Resource)]
+struct QuestCatalog {
+    quests: HashMap<String, QuestDefinition>,
+}
+
+#[derive(Resource)]
+struct QuestDefinitionsHandle(Handle<QuestDefinitions>);
+
+#[derive(Component)]
+struct QuestPanel;
+
+fn load_quest_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(QuestDefinitionsHandle(
+        asset_server.load(QUEST_DEFINITIONS_FILE),
+    ));
+}
+
+fn rebuild_quest_catalog(
+    mut commands: Commands,
+    mut asset_events: MessageReader<AssetEvent<QuestDefinitions>>,
+    handle: Res<QuestDefinitionsHandle>,
+    definitions: Res<Assets<QuestDefinitions>>,
+) {
+    let changed = asset_events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. }
+        )
+    });
+    if !changed {
+        return;
+    }
+    let Some(file) = definitions.get(&handle.0) else {
+        return;
+    };
+
+    info!("Quest catalog loaded: {} quests", file.0.len());
+    commands.insert_resource(QuestCatalog {
+        quests: file.0.clone(),
+    });
+}
+
+/// Replaces the board with a new selection at the start of each day.
+///
+/// The pick only depends on the simulation seed and the day number, so every run with the
+/// same seed offers the same quests.
+fn rotate_daily_quests(
+    clock: Res<GameClock>,
+    seed: Res<SimulationSeed>,
+    catalog: Res<QuestCatalog>,
+    mut board: ResMut<QuestBoard>,
+    mut notifications: ResMut<Notifications>,
+) {
+    let day = clock.day();
+    if board.day == day {
+        return;
+    }
+
+    let mut ids: Vec<_> = catalog.quests.keys().cloned().collect();
+    ids.sort();
+    let mut rng =
+        ChaCha8Rng::seed_from_u64(seed.0 ^ (day as u64).wrapping_mul(0xD1B5_4A32_D192_ED03));
+    ids.shuffle(&mut rng);
+    ids.truncate(QUESTS_PER_DAY);
+
+    info!("Day {} quests: {:?}", day, ids);
+    *board = QuestBoard {
+        day,
+        offered: ids,
+        ..default()
+    };
+    notifications
+        .push(Notification::new("New tasks on the board").with_body("Visit the Nutrition House."));
+}
+
+/// Drops quests whose deadline passed today.
+fn expire_quests(
+    clock: Res<GameClock>,
+    catalog: Res<QuestCatalog>,
+    mut board: ResMut<QuestBoard>,
+    mut notifications: ResMut<Notifications>,
+) {
+    let now = clock.minute_of_day();
+    let expired = |id: &String| {
+        catalog
+            .quests
+            .get(id)
+            .and_then(|quest| quest.deadline)
+            .is_some_and(|deadline| now >= deadline)
+    };
+    if !board.offered.iter().any(expired) && !board.active.iter().any(|q| expired(&q.id)) {
+        return;
+    }
+
+    board.offered.retain(|id| !expired(id));
+    let (failed, active): (Vec<_>, Vec<_>) =
+        board.active.drain(..).partition(|quest| expired(&quest.id));
+    board.active = active;
+
+    for quest in failed {
+        info!("Quest failed: {}", quest.id);
+        let title = &catalog.quests[&quest.id].title;
+        notifications.push(Notification::new(format!("Task failed: {}", title)));
+    }
+}
+
+/// Interacting with a quest giver's entrance hands in finished quests, then hands out the
+/// ones it offers.
+fn visit_quest_giver(
+    trigger: On<Interacted>,
+    players: Query<(), With<Player>>,
+    buildings: Query<&BuildingId>,
+    catalog: Option<Res<QuestCatalog>>,
+    counters: Res<Counters>,
+    mut board: ResMut<QuestBoard>,
+    mut economy: ResMut<Economy>,
+    mut notifications: ResMut<Notifications>,
+) {
+    let event = trigger.event();
+    let Some(catalog) = catalog else {
+        return;
+    };
+    if !players.contains(event.actor) {
+        return;
+    }
+    let Ok(building) = buildings.get(event.target) else {
+        return;
+    };
+    let giver = &building.id;
+
+    let mut still_active = Vec::new();
+    for quest in std::mem::take(&mut board.active) {
+        let Some(definition) = catalog.quests.get(&quest.id) else {
+            warn!("Dropping unknown quest: {}", quest.id);
+            continue;
+        };
+        let done = &definition.giver == giver
+            && match &definition.objective {
+                Objective::Counter { .. } | Objective::Visit { .. } => {
+                    let (current, target) =
+                        quest.progress(&definition.objective, &counters, &economy);
+                    current >= target
+                }
+                Objective::Deliver(cost) => economy.try_spend(*cost),
+            };
+        if !done {
+            still_active.push(quest);
+            continue;
+        }
+
+        info!("Quest completed: {}", quest.id);
+        economy.receive(definition.reward);
+        notifications.push(
+            Notification::new(format!("Task complete: {}", definition.title))
+                .with_body(format!("Reward: {}", definition.reward)),
+        );
+        board.completed.push(quest.id);
+    }
+    board.active = still_active;
+
+    let (handed_out, offered): (Vec<_>, Vec<_>) = std::mem::take(&mut board.offered)
+        .into_iter()
+        .partition(|id| {
+            catalog
+                .quests
+                .get(id)
+                .is_some_and(|quest| &quest.giver == giver)
+        });
+    board.offered = offered;
+
+    for id in handed_out {
+        let definition = &catalog.quests[&id];
+        let baseline = match &definition.objective {
+            Objective::Counter { name, .. } => counters.get(name),
+            Objective::Visit { .. } | Objective::Deliver(_) => 0,
+        };
+        info!("Quest accepted: {}", id);
+        notifications.push(
+            Notification::new(format!("New task: {}", definition.title))
+                .with_body(definition.description.clone()),
+        );
+        board.active.push(ActiveQuest {
+            id,
+            baseline,
+            visited: HashSet::new(),
+        });
+    }
+}
+
+/// Counts the buildings the player enters towards active visit quests, once per building.
+fn record_visited_buildings(
+    trigger: On<EnteredBuildingEvent>,
+    players: Query<(), With<Player>>,
+    catalog: Option<Res<QuestCatalog>>,
+    mut board: ResMut<QuestBoard>,
+) {
+    let event = trigger.event();
+    let Some(catalog) = catalog else {
+        return;
+    };
+    if !players.contains(event.actor) {
+        return;
+    }
+
+    for quest in board.active.iter_mut() {
+        let visits = catalog
+            .quests
+            .get(&quest.id)
+            .is_some_and(|quest| matches!(quest.objective, Objective::Visit { .. }));
+        if visits && quest.visited.insert(event.building.id.clone()) {
+            info!("Quest {} visited {}", quest.id, event.building.id);
+        }
+    }
+}
+
+fn spawn_quest_panel(mut commands: Commands) {
+    commands.spawn((
+        QuestPanel,
+        panel_node(PanelSlot::Middle),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+    ));
+}
+
+fn toggle_quest_panel(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut panel: Single<&mut Node, With<QuestPanel>>,
+) {
+    if kb_input.just_pressed(TOGGLE_PANEL_KEY) {
+        panel.display = match panel.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+/// Rebuilds the panel's rows when it opens or the board or progress changes.
+fn refresh_quest_panel(
+    mut commands: Commands,
+    panel: Single<(Entity, Ref<Node>), With<QuestPanel>>,
+    catalog: Res<QuestCatalog>,
+    board: Res<QuestBoard>,
+    counters: Res<Counters>,
+    economy: Res<Economy>,
+    registry: Option<Res<BuildingRegistry>>,
+) {
+    let (panel, node) = panel.into_inner();
+    if node.display == Display::None
+        || !(node.is_changed() || board.is_changed() || counters.is_changed())
+    {
+        return;
+    }
+
+    let giver_name = |id: &str| {
+        registry
+            .as_ref()
+            .and_then(|registry| registry.get(id))
+            .map_or(id.to_string(), |building| building.display_name.clone())
+    };
+    let deadline = |quest: &QuestDefinition| {
+        quest.deadline.map_or(String::new(), |minute| {
+            format!(" by {:02}:{:02}", minute / 60, minute % 60)
+        })
+    };
+
+    let mut rows = vec![format!("Day {} tasks (Q to close)", board.day)];
+    for quest in board.active.iter() {
+        let Some(definition) = catalog.quests.get(&quest.id) else {
+            continue;
+        };
+        let (current, target) = quest.progress(&definition.objective, &counters, &economy);
+        rows.push(format!(
+            "{} {}/{}{}\n{}\nReward: {}",
+            definition.title,
+            current,
+            target,
+            deadline(definition),
+            definition.description,
+            definition.reward
+        ));
+    }
+    for id in board.offered.iter() {
+        let Some(definition) = catalog.quests.get(id) else {
+            continue;
+        };
+        rows.push(format!(
+            "{} (pick up at {}){}",
+            definition.title,
+            giver_name(&definition.giver),
+            deadline(definition)
+        ));
+    }
+    for definition in board
+        .completed
+        .iter()
+        .filter_map(|id| catalog.quests.get(id))
+    {
+        rows.push(format!("{} (done)", definition.title));
+    }
+
+    commands.entity(panel).despawn_children();
+    for row in rows {
+        commands.entity(panel).with_child(Text::new(row));
+    }
+}
@@ -0,0 +1,640 @@
+use std::{
+    env,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    time::Duration,
+};
+
+use bevy::{input::InputSystems, prelude::*, time::TimeUpdateStrategy};
+use bevy_ecs_tiled::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buildings::BuildingRegistry,
+    clock::{GameClock, SimulationSeed},
+    components::{
+        BuildingEntrance, BuildingId, Player, PlayerMovementSet, SyntheticInputSet, TileGroup,
+        TilePosition,
+    },
+    entrance::{EnteredBuildingEvent, ExitedBuildingEvent},
+    interaction::Interacted,
+    save::SavingDisabled,
+};
+
+/// Keys recorded as game actions. Mouse and gamepad input are not recorded, so sessions
+/// relying on clicks (placing buildings, panel buttons) will not replay faithfully.
+const ACTION_KEYS: [(InputAction, KeyCode); 14] = [
+    (InputAction::MoveUp, KeyCode::KeyW),
+    (InputAction::MoveDown, KeyCode::KeyS),
+    (InputAction::MoveLeft, KeyCode::KeyA),
+    (InputAction::MoveRight, KeyCode::KeyD),
+    (InputAction::Interact, KeyCode::KeyE),
+    (InputAction::SpeedUp, KeyCode::BracketRight),
+    (InputAction::SlowDown, KeyCode::BracketLeft),
+    (InputAction::ResetSpeed, KeyCode::Backslash),
+    (InputAction::ToggleBuildMode, KeyCode::KeyB),
+    (InputAction::NextBuilding, KeyCode::Tab),
+    (InputAction::Choice1, KeyCode::Digit1),
+    (InputAction::Choice2, KeyCode::Digit2),
+    (InputAction::Choice3, KeyCode::Digit3),
+    (InputAction::Choice4, KeyCode::Digit4),
+];
+
+/// Records a session's input, or replays a recording and checks it plays out the same.
+///
+/// Both start a new game with saving disabled, and start their timeline once the map and
+/// building registry have loaded so asset loading times do not shift it.
+pub enum ReplayPlugin {
+    /// Records the session, writing it to the path (if any) on exit.
+    Record(Option<PathBuf>),
+    /// Replays a recording, then exits with an error if it diverged. Must be added after
+    /// the clock plugin so the recorded seed replaces the random one.
+    Replay(Recording),
+}
+
+impl ReplayPlugin {
+    /// Reads `--record <file>` or `--replay <file>` from the command line.
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = env::args().collect();
+        let value = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|index| args.get(index + 1))
+        };
+
+        if let Some(path) = value("--replay") {
+            let file = File::open(path)
+                .unwrap_or_else(|error| panic!("Failed to open replay {}: {}", path, error));
+            let recording = serde_json::from_reader(BufReader::new(file))
+                .unwrap_or_else(|error| panic!("Failed to parse replay {}: {}", path, error));
+            return Some(Self::Replay(recording));
+        }
+        value("--record").map(|path| Self::Record(Some(path.into())))
+    }
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SavingDisabled)
+            .init_resource::<Timeline>()
+            .configure_sets(PreUpdate, SyntheticInputSet.after(InputSystems))
+            .add_systems(Update, trace_player_moves.after(PlayerMovementSet))
+            .add_observer(mark_map_ready)
+            .add_observer(trace_entered_buildings)
+            .add_observer(trace_exited_buildings)
+            .add_observer(trace_interactions);
+
+        match self {
+            ReplayPlugin::Record(path) => {
+                app.insert_resource(Recorder {
+                    path: path.clone(),
+                    recording: Recording::default(),
+                })
+                .add_systems(PreUpdate, record_inputs.after(InputSystems))
+                .add_systems(
+                    Last,
+                    (advance_timeline, start_recording, write_recording_on_exit).chain(),
+                );
+            }
+            ReplayPlugin::Replay(recording) => {
+                info!(
+                    "Replaying {} frames with seed {}",
+                    recording.frame_times.len(),
+                    recording.seed
+                );
+                app.insert_resource(SimulationSeed(recording.seed))
+                    .insert_resource(Replayer::new(recording.clone()))
+                    .add_systems(PreUpdate, apply_replay_inputs.in_set(SyntheticInputSet))
+                    .add_systems(
+                        Last,
+                        (check_replay, finish_replay, advance_timeline, start_replay).chain(),
+                    );
+            }
+        }
+    }
+}
+
+/// A recorded session: everything needed to play it again, and what happened.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub start_minutes: f64,
+    /// Simulated time of every frame, in nanoseconds.
+    pub frame_times: Vec<u64>,
+    pub inputs: Vec<InputRecord>,
+    /// Compared against the replay to detect divergence.
+    pub trace: Vec<TraceEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputRecord {
+    pub frame: u64,
+    pub action: InputAction,
+    pub pressed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Interact,
+    SpeedUp,
+    SlowDown,
+    ResetSpeed,
+    ToggleBuildMode,
+    NextBuilding,
+    Choice1,
+    Choice2,
+    Choice3,
+    Choice4,
+}
+
+impl InputAction {
+    fn key(self) -> KeyCode {
+        ACTION_KEYS
+            .iter()
+            .find(|(action, _)| *action == self)
+            .map(|(_, key)| *key)
+            .expect("every action has a key")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub frame: u64,
+    pub event: TraceEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceEvent {
+    PlayerMoved { x: u32, y: u32 },
+    EnteredBuilding { actor: String, building: String },
+    ExitedBuilding { actor: String, building: String },
+    Interacted { target: String },
+}
+
+/// Where a replay first stopped matching its recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub expected: Option<TraceEntry>,
+    pub actual: Option<TraceEntry>,
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.expected, &self.actual) {
+            (Some(expected), Some(actual)) => write!(
+                f,
+                "expected {:?} at frame {}, got {:?} at frame {}",
+                expected.event, expected.frame, actual.event, actual.frame
+            ),
+            (Some(expected), None) => write!(
+                f,
+                "expected {:?} at frame {}, but it did not happen",
+                expected.event, expected.frame
+            ),
+            (None, Some(actual)) => {
+                write!(f, "unexpected {:?} at frame {}", actual.event, actual.frame)
+            }
+            (None, None) => write!(f, "no difference"),
+        }
+    }
+}
+
+/// Frame counter and event trace shared by recording and replay.
+#[derive(Resource, Default)]
+pub struct Timeline {
+    map_ready: bool,
+    started: bool,
+    frame: u64,
+    trace: Vec<TraceEntry>,
+}
+
+impl Timeline {
+    fn push(&mut self, event: TraceEvent) {
+        if self.started {
+            self.trace.push(TraceEntry {
+                frame: self.frame,
+                event,
+            });
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Recorder {
+    path: Option<PathBuf>,
+    recording: Recording,
+}
+
+impl Recorder {
+    /// The session so far, including its trace.
+    pub fn snapshot(&self, timeline: &Timeline) -> Recording {
+        Recording {
+            trace: timeline.trace.clone(),
+            ..self.recording.clone()
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Replayer {
+    recording: Recording,
+    next_input: usize,
+    /// Trace entries already compared.
+    checked: usize,
+    finished: bool,
+    divergence: Option<Divergence>,
+}
+
+impl Replayer {
+    fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            next_input: 0,
+            checked: 0,
+            finished: false,
+            divergence: None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The first mismatch with the recording, if any.
+    pub fn divergence(&self) -> Option<&Divergence> {
+        self.divergence.as_ref()
+    }
+}
+
+/// Map entrances still waiting for the building registry.
+type UnsnappedEntrances<'w, 's> = Query<
+    'w,
+    's,
+    (),
+    (
+        Or<(With<BuildingEntrance>, With<BuildingId>)>,
+        Without<TileGroup>,
+    ),
+>;
+
+/// Recording and replay wait for the map and building registry so that loading times do
+/// not shift the timeline.
+fn is_world_ready(
+    timeline: &Timeline,
+    registry: Option<&BuildingRegistry>,
+    unsnapped_entrances: &UnsnappedEntrances,
+) -> bool {
+    timeline.map_ready && registry.is_some() && unsnapped_entrances.is_empty()
+}
+
+fn mark_map_ready(_trigger: On<TiledEvent<MapCreated>>, mut timeline: ResMut<Timeline>) {
+    timeline.map_ready = true;
+}
+
+fn actor_label(entity: Entity, players: &Query<(), With<Player>>, names: &Query<&Name>) -> String {
+    if players.contains(entity) {
+        "player".to_string()
+    } else {
+        names
+            .get(entity)
+            .map_or_else(|_| format!("{:?}", entity), |name| name.to_string())
+    }
+}
+
+fn trace_player_moves(
+    player: Query<&TilePosition, (With<Player>, Changed<TilePosition>)>,
+    mut timeline: ResMut<Timeline>,
+) {
+    for position in player.iter() {
+        timeline.push(TraceEvent::PlayerMoved {
+            x: position.x,
+            y: position.y,
+        });
+    }
+}
+
+fn trace_entered_buildings(
+    trigger: On<EnteredBuildingEvent>,
+    players: Query<(), With<Player>>,
+    names: Query<&Name>,
+    mut timeline: ResMut<Timeline>,
+) {
+    let event = trigger.event();
+    timeline.push(TraceEvent::EnteredBuilding {
+        actor: actor_label(event.actor, &players, &names),
+        building: event.building.id.clone(),
+    });
+}
+
+fn trace_exited_buildings(
+    trigger: On<ExitedBuildingEvent>,
+    players: Query<(), With<Player>>,
+    names: Query<&Name>,
+    mut timeline: ResMut<Timeline>,
+) {
+    let event = trigger.event();
+    timeline.push(TraceEvent::ExitedBuilding {
+        actor: actor_label(event.actor, &players, &names),
+        building: event.building.id.clone(),
+    });
+}
+
+fn trace_interactions(
+    trigger: On<Interacted>,
+    players: Query<(), With<Player>>,
+    names: Query<&Name>,
+    buildings: Query<&BuildingId>,
+    mut timeline: ResMut<Timeline>,
+) {
+    let target = trigger.event().target;
+    let target = buildings.get(target).map_or_else(
+        |_| actor_label(target, &players, &names),
+        |building| building.id.clone(),
+    );
+    timeline.push(TraceEvent::Interacted { target });
+}
+
+fn advance_timeline(mut timeline: ResMut<Timeline>) {
+    if timeline.started {
+        timeline.frame += 1;
+    }
+}
+
+fn start_recording(
+    mut timeline: ResMut<Timeline>,
+    mut recorder: ResMut<Recorder>,
+    registry: Option<Res<BuildingRegistry>>,
+    unsnapped_entrances: UnsnappedEntrances,
+    seed: Option<Res<SimulationSeed>>,
+    clock: Option<Res<GameClock>>,
+) {
+    if timeline.started || !is_world_ready(&timeline, registry.as_deref(), &unsnapped_entrances) {
+        return;
+    }
+
+    recorder.recording.seed = seed.map_or(0, |seed| seed.0);
+    recorder.recording.start_minutes = clock.map_or(0.0, |clock| clock.total_minutes());
+    // Runs after the timeline advanced, so the next frame is frame 0.
+    timeline.started = true;
+    info!("Recording started");
+}
+
+fn record_inputs(
+    timeline: Res<Timeline>,
+    mut recorder: ResMut<Recorder>,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+) {
+    if !timeline.started {
+        return;
+    }
+    let frame = timeline.frame;
+    let recording = &mut recorder.recording;
+    recording.frame_times.push(time.delta().as_nanos() as u64);
+
+    for (action, key) in ACTION_KEYS {
+        // Keys already held when recording started count as pressed on the first frame.
+        let pressed = kb_input.just_pressed(key) || (frame == 0 && kb_input.pressed(key));
+        if pressed {
+            recording.inputs.push(InputRecord {
+                frame,
+                action,
+                pressed: true,
+            });
+        } else if kb_input.just_released(key) {
+            recording.inputs.push(InputRecord {
+                frame,
+                action,
+                pressed: false,
+            });
+        }
+    }
+}
+
+fn write_recording_on_exit(
+    mut exits: MessageReader<AppExit>,
+    recorder: Res<Recorder>,
+    timeline: Res<Timeline>,
+) {
+    if exits.read().next().is_none() {
+        return;
+    }
+    let Some(path) = &recorder.path else {
+        return;
+    };
+
+    let recording = recorder.snapshot(&timeline);
+    let result = File::create(path)
+        .map_err(|error| error.to_string())
+        .and_then(|file| {
+            serde_json::to_writer(BufWriter::new(file), &recording)
+                .map_err(|error| error.to_string())
+        });
+    match result {
+        Ok(()) => info!(
+            "Saved {} recorded frames to {}",
+            recording.frame_times.len(),
+            path.display()
+        ),
+        Err(error) => warn!("Failed to save recording to {}: {}", path.display(), error),
+    }
+}
+
+fn start_replay(
+    mut timeline: ResMut<Timeline>,
+    replayer: Res<Replayer>,
+    registry: Option<Res<BuildingRegistry>>,
+    unsnapped_entrances: UnsnappedEntrances,
+    clock: Option<ResMut<GameClock>>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+) {
+    if timeline.started || !is_world_ready(&timeline, registry.as_deref(), &unsnapped_entrances) {
+        return;
+    }
+
+    let recording = &replayer.recording;
+    if let Some(mut clock) = clock {
+        clock.restore(recording.start_minutes);
+    }
+    if let Some(&nanos) = recording.frame_times.first() {
+        *time_update = TimeUpdateStrategy::ManualDuration(Duration::from_nanos(nanos));
+    }
+    timeline.started = true;
+    info!("Replay started");
+}
+
+fn apply_replay_inputs(
+    timeline: Res<Timeline>,
+    mut replayer: ResMut<Replayer>,
+    mut kb_input: ResMut<ButtonInput<KeyCode>>,
+) {
+    if !timeline.started || replayer.finished {
+        return;
+    }
+
+    while let Some(&input) = replayer.recording.inputs.get(replayer.next_input) {
+        if input.frame != timeline.frame {
+            break;
+        }
+        if input.pressed {
+            kb_input.press(input.action.key());
+        } else {
+            kb_input.release(input.action.key());
+        }
+        replayer.next_input += 1;
+    }
+}
+
+/// Compares this frame's trace entries against the recording.
+fn check_replay(timeline: Res<Timeline>, mut replayer: ResMut<Replayer>) {
+    if !timeline.started || replayer.finished || replayer.divergence.is_some() {
+        return;
+    }
+
+    let last_frame = (replayer.recording.frame_times.len() as u64).saturating_sub(1);
+    while replayer.checked < timeline.trace.len() {
+        let actual = &timeline.trace[replayer.checked];
+        let expected = replayer.recording.trace.get(replayer.checked);
+        if expected != Some(actual) {
+            replayer.divergence = Some(Divergence {
+                expected: expected.cloned(),
+                actual: Some(actual.clone()),
+            });
+            return;
+        }
+        replayer.checked += 1;
+    }
+
+    // Entries recorded for this frame that the replay did not produce.
+    let missing = replayer
+        .recording
+        .trace
+        .get(replayer.checked)
+        .filter(|expected| expected.frame <= timeline.frame || timeline.frame == last_frame);
+    if let Some(expected) = missing {
+        replayer.divergence = Some(Divergence {
+            expected: Some(expected.clone()),
+            actual: None,
+        });
+    }
+}
+
+/// Feeds the next frame's recorded time, and exits once the recording has played out.
+fn finish_replay(
+    timeline: Res<Timeline>,
+    mut replayer: ResMut<Replayer>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+    mut exits: MessageWriter<AppExit>,
+) {
+    if !timeline.started || replayer.is_finished() {
+        return;
+    }
+    if replayer.divergence().is_none()
+        && let Some(&nanos) = replayer
+            .recording
+            .frame_times
+            .get(timeline.frame as usize + 1)
+    {
+        *time_update = TimeUpdateStrategy::ManualDuration(Duration::from_nanos(nanos));
+        return;
+    }
+
+    replayer.finished = true;
+    match replayer.divergence() {
+        Some(divergence) => {
+            error!("Replay diverged: {}", divergence);
+            exits.write(AppExit::error());
+        }
+        None => {
+            info!(
+                "Replay finished: {} frames, {} trace entries matched",
+                replayer.recording.frame_times.len(),
+                replayer.checked
+            );
+            exits.write(AppExit::Success);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    const TEST_MAP: &str = "test/entrance_test.tmx";
+
+    fn record_walk_into_entrance() -> Recording {
+        let mut app = TestApp::with_plugins(TEST_MAP, ReplayPlugin::Record(None));
+        app.wait_until("recording", |world| world.resource::<Timeline>().started);
+        app.walk(KeyCode::KeyD, 3);
+        app.walk(KeyCode::KeyW, 3);
+        app.step(10);
+
+        let world = app.world();
+        world
+            .resource::<Recorder>()
+            .snapshot(world.resource::<Timeline>())
+    }
+
+    fn replay(recording: Recording) -> Option<Divergence> {
+        let mut app = TestApp::with_plugins(TEST_MAP, ReplayPlugin::Replay(recording));
+        app.wait_until("replay", |world| world.resource::<Replayer>().is_finished());
+        app.world().resource::<Replayer>().divergence().cloned()
+    }
+
+    #[test]
+    fn recording_captures_inputs_and_trajectory() {
+        let recording = record_walk_into_entrance();
+
+        let moves = recording
+            .trace
+            .iter()
+            .filter(|entry| matches!(entry.event, TraceEvent::PlayerMoved { .. }))
+            .count();
+        assert_eq!(moves, 6);
+        assert!(recording.trace.iter().any(|entry| entry.event
+            == TraceEvent::EnteredBuilding {
+                actor: "player".to_string(),
+                building: "nutrition_house".to_string(),
+            }));
+        assert_eq!(
+            recording
+                .inputs
+                .iter()
+                .filter(|input| input.pressed)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn replay_reproduces_the_recording() {
+        let recording = record_walk_into_entrance();
+        assert_eq!(replay(recording), None);
+    }
+
+    #[test]
+    fn replay_reports_divergence() {
+        let mut recording = record_walk_into_entrance();
+        // Walk into the map edge instead of to the right.
+        for input in recording.inputs.iter_mut() {
+            if input.action == InputAction::MoveRight {
+                input.action = InputAction::MoveLeft;
+            }
+        }
+
+        let divergence = replay(recording).expect("the replay should diverge");
+        assert_eq!(
+            divergence.expected.map(|entry| entry.event),
+            Some(TraceEvent::PlayerMoved { x: 1, y: 0 })
+        );
+        assert_eq!(divergence.actual, None);
+    }
+}
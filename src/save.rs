@@ -0,0 +1,187 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::{Counters, UnlockedAchievements},
+    animals::AnimalNeeds,
+    clock::{DayStarted, GameClock, OfflineCatchUp, SimulationSeed},
+    construction::PlacedBuildings,
+    dialogue::DialogueFlags,
+    economy::Economy,
+    quests::QuestBoard,
+    upgrades::PurchasedUpgrades,
+    weather::WeatherState,
+};
+
+const SAVE_FILE: &str = "save.json";
+
+/// Loads the game on startup and saves it at the start of every day and on exit.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreStartup,
+            load_game.run_if(not(resource_exists::<SavingDisabled>)),
+        )
+        .add_systems(
+            Last,
+            save_on_exit.run_if(not(resource_exists::<SavingDisabled>)),
+        )
+        .add_observer(autosave_on_new_day);
+    }
+}
+
+/// Starts a new game and never writes the save, e.g. while recording or replaying input.
+#[derive(Resource)]
+pub struct SavingDisabled;
+
+/// Everything that survives a restart. Fields added after the first release default so
+/// older saves keep loading.
+#[derive(Serialize, Deserialize)]
+struct SaveData {
+    seed: u64,
+    total_minutes: f64,
+    weather: WeatherState,
+    #[serde(default)]
+    economy: Economy,
+    #[serde(default)]
+    upgrades: PurchasedUpgrades,
+    #[serde(default)]
+    placed_buildings: PlacedBuildings,
+    #[serde(default)]
+    counters: Counters,
+    #[serde(default)]
+    achievements: UnlockedAchievements,
+    #[serde(default)]
+    quests: QuestBoard,
+    #[serde(default)]
+    dialogue_flags: DialogueFlags,
+    /// Wall-clock time of the save in seconds since the Unix epoch, to catch up on the
+    /// time spent offline.
+    #[serde(default)]
+    saved_at: Option<u64>,
+    #[serde(default)]
+    animal_needs: AnimalNeeds,
+}
+
+fn unix_seconds() -> Option<u64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|elapsed| elapsed.as_secs())
+}
+
+/// The resources a save is taken from.
+#[derive(SystemParam)]
+struct SavedState<'w> {
+    seed: Res<'w, SimulationSeed>,
+    clock: Res<'w, GameClock>,
+    weather: Res<'w, WeatherState>,
+    economy: Res<'w, Economy>,
+    upgrades: Res<'w, PurchasedUpgrades>,
+    placed_buildings: Res<'w, PlacedBuildings>,
+    counters: Res<'w, Counters>,
+    achievements: Res<'w, UnlockedAchievements>,
+    quests: Res<'w, QuestBoard>,
+    dialogue_flags: Res<'w, DialogueFlags>,
+    animal_needs: Res<'w, AnimalNeeds>,
+}
+
+impl SavedState<'_> {
+    fn write(&self) {
+        let data = SaveData {
+            seed: self.seed.0,
+            total_minutes: self.clock.total_minutes(),
+            weather: *self.weather,
+            economy: self.economy.clone(),
+            upgrades: self.upgrades.clone(),
+            placed_buildings: self.placed_buildings.clone(),
+            counters: self.counters.clone(),
+            achievements: self.achievements.clone(),
+            quests: self.quests.clone(),
+            dialogue_flags: self.dialogue_flags.clone(),
+            saved_at: unix_seconds(),
+            animal_needs: self.animal_needs.clone(),
+        };
+
+        let result = File::create(SAVE_FILE)
+            .map_err(|error| error.to_string())
+            .and_then(|file| {
+                serde_json::to_writer_pretty(BufWriter::new(file), &data)
+                    .map_err(|error| error.to_string())
+            });
+        match result {
+            Ok(()) => info!("Saved game to {}", SAVE_FILE),
+            Err(error) => warn!("Failed to save game to {}: {}", SAVE_FILE, error),
+        }
+    }
+}
+
+fn load_game(mut commands: Commands, mut clock: ResMut<GameClock>) {
+    let file = match File::open(SAVE_FILE) {
+        Ok(file) => file,
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            info!("No save found, starting a new game");
+            return;
+        }
+        Err(error) => {
+            warn!("Failed to open {}: {}", SAVE_FILE, error);
+            return;
+        }
+    };
+
+    let data: SaveData = match serde_json::from_reader(BufReader::new(file)) {
+        Ok(data) => data,
+        Err(error) => {
+            warn!("Ignoring unreadable save {}: {}", SAVE_FILE, error);
+            return;
+        }
+    };
+
+    info!("Loaded save from {}", SAVE_FILE);
+    clock.restore(data.total_minutes);
+    // The clock keeps running while the game is closed, at the normal speed.
+    let offline_seconds = data
+        .saved_at
+        .zip(unix_seconds())
+        .map_or(0, |(saved_at, now)| now.saturating_sub(saved_at));
+    if offline_seconds > 0 {
+        let minutes = offline_seconds as f64 * clock.minutes_per_second;
+        info!("Catching up on {:.0} minutes spent offline", minutes);
+        clock.advance(minutes);
+        commands.insert_resource(OfflineCatchUp { minutes });
+    }
+    commands.insert_resource(SimulationSeed(data.seed));
+    commands.insert_resource(data.weather);
+    commands.insert_resource(data.economy);
+    commands.insert_resource(data.upgrades);
+    commands.insert_resource(data.placed_buildings);
+    commands.insert_resource(data.counters);
+    commands.insert_resource(data.achievements);
+    commands.insert_resource(data.quests);
+    commands.insert_resource(data.dialogue_flags);
+    commands.insert_resource(data.animal_needs);
+}
+
+fn autosave_on_new_day(
+    _trigger: On<DayStarted>,
+    state: SavedState,
+    disabled: Option<Res<SavingDisabled>>,
+) {
+    if disabled.is_none() {
+        state.write();
+    }
+}
+
+fn save_on_exit(mut exits: MessageReader<AppExit>, state: SavedState) {
+    if exits.read().next().is_some() {
+        state.write();
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind},
+};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    camera::CameraConfig, notifications::NotificationConfig, player::PlayerConfig,
+    streaming::StreamingConfig,
+};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Overrides plugin configuration from an optional settings file on startup.
+///
+/// Each plugin inserts its config resource with the built-in defaults; sections present in
+/// the file replace them before `Startup`, and fields missing from a section keep their
+/// default value. Sections with values the game can't run with are ignored with a warning.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, load_settings);
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Settings {
+    camera: Option<CameraConfig>,
+    player: Option<PlayerConfig>,
+    notifications: Option<NotificationConfig>,
+    streaming: Option<StreamingConfig>,
+}
+
+fn load_settings(mut commands: Commands) {
+    let file = match File::open(SETTINGS_FILE) {
+        Ok(file) => file,
+        Err(error) if error.kind() == ErrorKind::NotFound => return,
+        Err(error) => {
+            warn!("Failed to open {}: {}", SETTINGS_FILE, error);
+            return;
+        }
+    };
+
+    let settings: Settings = match serde_json::from_reader(BufReader::new(file)) {
+        Ok(settings) => settings,
+        Err(error) => {
+            warn!("Ignoring unreadable settings {}: {}", SETTINGS_FILE, error);
+            return;
+        }
+    };
+
+    info!("Loaded settings from {}", SETTINGS_FILE);
+    if let Some(camera) = settings.camera.and_then(|c| checked("camera", c)) {
+        commands.insert_resource(camera);
+    }
+    if let Some(player) = settings.player.and_then(|c| checked("player", c)) {
+        commands.insert_resource(player);
+    }
+    if let Some(notifications) = settings
+        .notifications
+        .and_then(|c| checked("notifications", c))
+    {
+        commands.insert_resource(notifications);
+    }
+    if let Some(streaming) = settings.streaming.and_then(|c| checked("streaming", c)) {
+        commands.insert_resource(streaming);
+    }
+}
+
+/// A settings section that can hold values the game can't run with.
+trait Validate {
+    /// Describes the first invalid value, if any.
+    fn problem(&self) -> Option<&'static str>;
+}
+
+impl Validate for CameraConfig {
+    fn problem(&self) -> Option<&'static str> {
+        (!(self.decay_rate.is_finite() && self.decay_rate >= 0.0))
+            .then_some("decay_rate must be zero or more")
+    }
+}
+
+impl Validate for PlayerConfig {
+    fn problem(&self) -> Option<&'static str> {
+        (!(self.step_cooldown.is_finite() && self.step_cooldown >= 0.0))
+            .then_some("step_cooldown must be zero or more")
+    }
+}
+
+impl Validate for NotificationConfig {
+    fn problem(&self) -> Option<&'static str> {
+        if self.max_visible == 0 {
+            Some("max_visible must be at least 1")
+        } else if !(self.width > 0.0 && self.height > 0.0) {
+            Some("width and height must be more than zero")
+        } else {
+            None
+        }
+    }
+}
+
+impl Validate for StreamingConfig {
+    fn problem(&self) -> Option<&'static str> {
+        (self.chunk_size == 0).then_some("chunk_size must be at least 1")
+    }
+}
+
+/// Keeps a section only if all of its values are usable, so the defaults stay in place
+/// otherwise.
+fn checked<T: Validate>(section: &str, config: T) -> Option<T> {
+    match config.problem() {
+        Some(problem) => {
+            warn!("Ignoring {} settings: {}", section, problem);
+            None
+        }
+        None => Some(config),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_keep_their_defaults() {
+        let settings: Settings =
+            serde_json::from_str(r#"{ "notifications": { "max_visible": 5 } }"#).unwrap();
+
+        let notifications = settings.notifications.unwrap();
+        assert_eq!(notifications.max_visible, 5);
+        assert_eq!(notifications.width, NotificationConfig::default().width);
+        assert!(settings.camera.is_none());
+        assert!(settings.player.is_none());
+    }
+
+    #[test]
+    fn invalid_sections_keep_the_defaults() {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "player": { "step_cooldown": -1.0 },
+                "notifications": { "max_visible": 0 },
+                "streaming": { "radius": 4 }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(checked("player", settings.player.unwrap()).is_none());
+        assert!(checked("notifications", settings.notifications.unwrap()).is_none());
+        assert_eq!(
+            checked("streaming", settings.streaming.unwrap())
+                .unwrap()
+                .radius,
+            4
+        );
+    }
+
+    #[test]
+    fn unknown_sections_are_rejected() {
+        assert!(serde_json::from_str::<Settings>(r#"{ "camra": {} }"#).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    components::{Facing, MovementCooldown, PlayerMovementSet, TilePosition},
+    data::JsonAssetAppExt,
+};
+
+pub struct SpriteAnimationPlugin;
+
+impl Plugin for SpriteAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_json_asset::<SpriteAnimationSet>(&["anim.json"])
+            .add_systems(
+                Update,
+                (
+                    reset_reloaded_animators,
+                    attach_animated_sprites,
+                    animate_sprites,
+                )
+                    .chain()
+                    .after(PlayerMovementSet),
+            );
+    }
+}
+
+/// A sprite sheet and its named clips, loaded from an `*.anim.json` file.
+///
+/// Clips are looked up as `"{idle|walk}_{up|down|left|right}"`.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct SpriteAnimationSet {
+    pub image: String,
+    pub frame_size: [u32; 2],
+    pub columns: u32,
+    pub rows: u32,
+    pub clips: HashMap<String, SpriteClip>,
+}
+
+impl SpriteAnimationSet {
+    /// Clips with a frame index past the end of the sprite sheet, sorted by name.
+    fn clips_out_of_range(&self) -> Vec<&str> {
+        let frame_count = (self.columns * self.rows) as usize;
+        let mut clips: Vec<&str> = self
+            .clips
+            .iter()
+            .filter(|(_, clip)| clip.frames.iter().any(|frame| *frame >= frame_count))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        clips.sort();
+        clips
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SpriteClip {
+    /// Indices into the sprite sheet grid, left-to-right then top-to-bottom.
+    pub frames: Vec<usize>,
+    pub fps: f32,
+}
+
+/// Plays the idle/walk clip matching the entity's [`Facing`] and movement phase.
+#[derive(Component)]
+#[require(Facing)]
+pub struct SpriteAnimator {
+    pub animations: Handle<SpriteAnimationSet>,
+    clip: String,
+    frame: usize,
+    timer: Timer,
+    /// The tile seen last frame, to notice steps.
+    last_tile: Option<TilePosition>,
+    /// Keeps the walk clip playing for one step after the actor moved.
+    walking: Timer,
+}
+
+impl SpriteAnimator {
+    pub fn new(animations: Handle<SpriteAnimationSet>) -> Self {
+        Self {
+            animations,
+            clip: String::new(),
+            frame: 0,
+            timer: Timer::default(),
+            last_tile: None,
+            walking: Timer::default(),
+        }
+    }
+}
+
+/// Drops the sprite of animators whose definition changed so it gets rebuilt.
+fn reset_reloaded_animators(
+    mut asset_events: MessageReader<AssetEvent<SpriteAnimationSet>>,
+    animators: Query<(Entity, &SpriteAnimator), With<Sprite>>,
+    mut commands: Commands,
+) {
+    for event in asset_events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+
+        for (entity, animator) in animators.iter() {
+            if animator.animations.id() == *id {
+                commands.entity(entity).remove::<Sprite>();
+            }
+        }
+    }
+}
+
+/// Builds the atlas sprite once the animator's definition has loaded.
+fn attach_animated_sprites(
+    mut animators: Query<(Entity, &mut SpriteAnimator), Without<Sprite>>,
+    animation_sets: Res<Assets<SpriteAnimationSet>>,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut commands: Commands,
+) {
+    for (entity, mut animator) in animators.iter_mut() {
+        let Some(set) = animation_sets.get(&animator.animations) else {
+            continue;
+        };
+        let out_of_range = set.clips_out_of_range();
+        if !out_of_range.is_empty() {
+            warn!(
+                "Sprite animation clips {:?} use frames past the {}x{} sprite sheet, which are skipped",
+                out_of_range, set.columns, set.rows
+            );
+        }
+
+        let layout = TextureAtlasLayout::from_grid(
+            UVec2::from(set.frame_size),
+            set.columns,
+            set.rows,
+            None,
+            None,
+        );
+        commands.entity(entity).insert(Sprite::from_atlas_image(
+            asset_server.load(&set.image),
+            TextureAtlas {
+                layout: layouts.add(layout),
+                index: 0,
+            },
+        ));
+
+        // Force the current clip to be re-resolved against the new definition.
+        animator.clip.clear();
+    }
+}
+
+/// Advances animation frames.
+///
+/// An actor counts as walking for one [`MovementCooldown`] after its [`TilePosition`]
+/// changed, so bumping into a wall or standing still after spawning plays the idle clip.
+fn animate_sprites(
+    mut animators: Query<(
+        &mut SpriteAnimator,
+        &Facing,
+        Option<&TilePosition>,
+        Option<&MovementCooldown>,
+        &mut Sprite,
+    )>,
+    animation_sets: Res<Assets<SpriteAnimationSet>>,
+    time: Res<Time>,
+) {
+    for (mut animator, facing, tile, cooldown, mut sprite) in animators.iter_mut() {
+        animator.walking.tick(time.delta());
+        let stepped = animator.last_tile.is_some() && animator.last_tile != tile.copied();
+        animator.last_tile = tile.copied();
+        if stepped {
+            let step = cooldown.map_or(Duration::ZERO, |cooldown| cooldown.0.duration());
+            animator.walking = Timer::new(step, TimerMode::Once);
+        }
+
+        let Some(set) = animation_sets.get(&animator.animations) else {
+            continue;
+        };
+
+        let walking = stepped || !animator.walking.is_finished();
+        let clip_name = format!(
+            "{}_{}",
+            if walking { "walk" } else { "idle" },
+            facing.name()
+        );
+
+        let Some(clip) = set.clips.get(&clip_name) else {
+            warn_once!("Missing sprite animation clip: {}", clip_name);
+            continue;
+        };
+        if clip.frames.is_empty() {
+            continue;
+        }
+
+        if animator.clip != clip_name {
+            animator.clip = clip_name;
+            animator.frame = 0;
+            animator.timer = Timer::from_seconds(1.0 / clip.fps.max(0.01), TimerMode::Repeating);
+        } else {
+            animator.timer.tick(time.delta());
+            let advanced = animator.timer.times_finished_this_tick() as usize;
+            animator.frame = (animator.frame + advanced) % clip.frames.len();
+        }
+
+        let index = clip.frames[animator.frame];
+        if index >= (set.columns * set.rows) as usize {
+            continue;
+        }
+        if sprite
+            .texture_atlas
+            .as_ref()
+            .is_some_and(|atlas| atlas.index != index)
+            && let Some(atlas) = sprite.texture_atlas.as_mut()
+        {
+            atlas.index = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{Player, Solid, TileRect},
+        entrance::SpawnZoneExt,
+        testing::TestApp,
+    };
+
+    fn player_clip(app: &mut TestApp) -> String {
+        let world = app.world();
+        world
+            .query_filtered::<&SpriteAnimator, With<Player>>()
+            .single(world)
+            .unwrap()
+            .clip
+            .clone()
+    }
+
+    #[test]
+    fn only_actual_steps_play_the_walk_cycle() {
+        let mut app = TestApp::with_plugins("test/entrance_test.tmx", SpriteAnimationPlugin);
+        let world = app.world();
+        world
+            .commands()
+            .spawn_zone(TileRect::new(TilePosition { x: 0, y: 1 }, 1, 1), Solid);
+        world.flush();
+        app.wait_for_zones(2);
+        app.wait_until("player sprite", |world| {
+            world
+                .query_filtered::<&SpriteAnimator, (With<Player>, With<Sprite>)>()
+                .iter(world)
+                .any(|animator| !animator.clip.is_empty())
+        });
+        assert_eq!(player_clip(&mut app), "idle_down");
+
+        app.press(KeyCode::KeyW);
+        app.step(2);
+        app.release(KeyCode::KeyW);
+        assert_eq!(player_clip(&mut app), "idle_up");
+
+        app.press(KeyCode::KeyD);
+        app.step(2);
+        app.release(KeyCode::KeyD);
+        assert_eq!(player_clip(&mut app), "walk_right");
+    }
+
+    #[test]
+    fn frames_past_the_sprite_sheet_are_reported() {
+        let set: SpriteAnimationSet = serde_json::from_str(
+            r#"{
+                "image": "player.png",
+                "frame_size": [32, 32],
+                "columns": 2,
+                "rows": 2,
+                "clips": {
+                    "idle_down": { "frames": [0, 3], "fps": 1.0 },
+                    "walk_down": { "frames": [2, 4], "fps": 1.0 }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(set.clips_out_of_range(), ["walk_down"]);
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+use serde::Deserialize;
+
+use crate::map::MapGrids;
+
+/// Streams the tiles of infinite maps in and out around the camera.
+///
+/// Once a tilemap of an infinite map is spawned, its plain tiles are kept as data per
+/// chunk and only spawned while their chunk is near the camera. Tiles with properties or
+/// animations stay spawned, and so do zones, collision and the other Tiled objects, so
+/// gameplay never depends on where the camera is.
+///
+/// bevy_ecs_tiled has no way to leave tile layers out of its first spawn, so a map still
+/// spawns every tile once; the far ones are despawned right away.
+pub struct StreamingPlugin;
+
+impl Plugin for StreamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StreamingConfig>()
+            .add_observer(start_streaming)
+            .add_systems(Update, stream_chunks);
+    }
+}
+
+/// Chunk streaming tuning, overridable from the settings file.
+#[derive(Resource, Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StreamingConfig {
+    /// Width and height of a chunk in tiles. Matches Tiled's chunks by default.
+    pub chunk_size: u32,
+    /// Chunks kept spawned on each side of the one the camera is over.
+    pub radius: u32,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 16,
+            radius: 2,
+        }
+    }
+}
+
+/// What it takes to spawn a plain tile again.
+#[derive(Debug, Clone, Copy)]
+struct StreamedTile {
+    position: TilePos,
+    texture: TileTextureIndex,
+    flip: TileFlip,
+}
+
+/// The plain tiles of a tilemap on an infinite map, by chunk.
+#[derive(Component, Default)]
+struct ChunkStore {
+    tiles: HashMap<UVec2, Vec<StreamedTile>>,
+    /// Tile entities of the chunks that are spawned.
+    spawned: HashMap<UVec2, Vec<Entity>>,
+}
+
+fn chunk_of(position: TilePos, chunk_size: u32) -> UVec2 {
+    UVec2::new(position.x, position.y) / chunk_size.max(1)
+}
+
+/// Takes the plain tiles of a new tilemap on an infinite map out of the world and into
+/// its [`ChunkStore`]. The chunks around the camera are spawned again on the next update.
+fn start_streaming(
+    trigger: On<TiledEvent<TilemapCreated>>,
+    mut commands: Commands,
+    config: Res<StreamingConfig>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    mut tilemaps: Query<&mut TileStorage>,
+    tiles: Query<(&TilePos, &TileTextureIndex, &TileFlip)>,
+) {
+    let event = trigger.event();
+    let Some(asset) = event.get_map_asset(&map_assets) else {
+        return;
+    };
+    if !asset.map.infinite() {
+        return;
+    }
+    let (Some(tilemap), Some(tileset)) =
+        (event.get_tilemap_entity(), event.get_tilemap_tileset_id())
+    else {
+        return;
+    };
+    let Some(layer) = event
+        .get_layer(&map_assets)
+        .and_then(|layer| layer.as_tile_layer())
+    else {
+        return;
+    };
+    let Ok(mut storage) = tilemaps.get_mut(tilemap) else {
+        return;
+    };
+
+    // Tiles with properties or animations carry more than a sprite; leave them be.
+    let mut kept = HashSet::new();
+    asset.for_each_tile(&layer, |layer_tile, _, position, _| {
+        let special = layer_tile
+            .get_tile()
+            .is_some_and(|tile| !tile.properties.is_empty() || tile.animation.is_some());
+        if special && layer_tile.tileset_index() == tileset as usize {
+            kept.insert(position);
+        }
+    });
+
+    let mut store = ChunkStore::default();
+    let entities: Vec<Entity> = storage.iter().flatten().copied().collect();
+    for entity in entities {
+        let Ok((&position, &texture, &flip)) = tiles.get(entity) else {
+            continue;
+        };
+        if kept.contains(&position) {
+            continue;
+        }
+        store
+            .tiles
+            .entry(chunk_of(position, config.chunk_size))
+            .or_default()
+            .push(StreamedTile {
+                position,
+                texture,
+                flip,
+            });
+        storage.remove(&position);
+        commands.entity(entity).despawn();
+    }
+    commands.entity(tilemap).insert(store);
+    info!("Streaming the tiles of tilemap {:?}", tilemap);
+}
+
+/// Despawns the chunks that left the camera's radius and spawns the ones that entered it.
+fn stream_chunks(
+    mut commands: Commands,
+    config: Res<StreamingConfig>,
+    camera: Single<&GlobalTransform, With<Camera2d>>,
+    grids: MapGrids,
+    mut tilemaps: Query<(Entity, &mut ChunkStore, &mut TileStorage)>,
+) {
+    let camera = camera.translation().truncate();
+    for (tilemap, mut store, mut storage) in tilemaps.iter_mut() {
+        let Some(grid) = grids.of(tilemap) else {
+            continue;
+        };
+        // Below or left of the map, the nearest chunks are the first ones.
+        let center = grid.world_to_tile(camera).map_or(UVec2::ZERO, |tile| {
+            chunk_of(TilePos::new(tile.x, tile.y), config.chunk_size)
+        });
+        let in_range = |chunk: UVec2| {
+            (chunk.as_ivec2() - center.as_ivec2()).abs().max_element() <= config.radius as i32
+        };
+        let store = &mut *store;
+
+        store.spawned.retain(|chunk, entities| {
+            if in_range(*chunk) {
+                return true;
+            }
+            for &entity in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+            for tile in store.tiles.get(chunk).into_iter().flatten() {
+                storage.remove(&tile.position);
+            }
+            false
+        });
+
+        for (chunk, tiles) in store.tiles.iter() {
+            if !in_range(*chunk) || store.spawned.contains_key(chunk) {
+                continue;
+            }
+            let entities = tiles
+                .iter()
+                .map(|tile| {
+                    let entity = commands
+                        .spawn((
+                            TiledTile,
+                            TileBundle {
+                                position: tile.position,
+                                tilemap_id: TilemapId(tilemap),
+                                texture_index: tile.texture,
+                                flip: tile.flip,
+                                ..default()
+                            },
+                            ChildOf(tilemap),
+                        ))
+                        .id();
+                    storage.set(&tile.position, entity);
+                    entity
+                })
+                .collect();
+            store.spawned.insert(*chunk, entities);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    /// An infinite map of 2x2 chunks of 32px tiles.
+    const TEST_MAP: &str = "test/streaming_test.tmx";
+
+    /// Positions of the spawned tiles.
+    fn spawned_tiles(world: &mut World) -> Vec<TilePos> {
+        world.query::<&TilePos>().iter(world).copied().collect()
+    }
+
+    /// The tile spawned at `position`, through the tilemap's storage.
+    fn tile_at(world: &mut World, position: TilePos) -> Option<Entity> {
+        let storage = world.query::<&TileStorage>().single(world).unwrap();
+        storage.get(&position)
+    }
+
+    #[test]
+    fn only_chunks_around_the_camera_are_spawned() {
+        let mut app = TestApp::with_plugins(TEST_MAP, StreamingPlugin);
+        app.world().insert_resource(StreamingConfig {
+            chunk_size: 16,
+            radius: 0,
+        });
+        let camera = app.world().spawn((Camera2d, Transform::default())).id();
+        let near = TilePos { x: 4, y: 4 };
+
+        app.wait_until("far chunks streamed out", |world| {
+            let tiles = spawned_tiles(world);
+            tiles.len() == 16 * 16 && tiles.iter().all(|tile| tile.x < 16 && tile.y < 16)
+        });
+        let tile = tile_at(app.world(), near).unwrap();
+        let texture = *app.world().get::<TileTextureIndex>(tile).unwrap();
+
+        // Over the top right chunk.
+        app.world()
+            .entity_mut(camera)
+            .insert(Transform::from_xyz(24.5 * 32.0, 24.5 * 32.0, 0.0));
+        app.wait_until("camera chunk streamed in", |world| {
+            let tiles = spawned_tiles(world);
+            tiles.len() == 16 * 16 && tiles.iter().all(|tile| tile.x >= 16 && tile.y >= 16)
+        });
+        assert!(app.world().get_entity(tile).is_err());
+        assert_eq!(tile_at(app.world(), near), None);
+
+        // The tile is spawned again from the chunk data, looking the same.
+        app.world().entity_mut(camera).insert(Transform::default());
+        app.wait_until("first chunk streamed back in", |world| {
+            tile_at(world, near).is_some()
+        });
+        let tile = tile_at(app.world(), near).unwrap();
+        assert_eq!(app.world().get::<TileTextureIndex>(tile), Some(&texture));
+    }
+}
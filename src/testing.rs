@@ -0,0 +1,190 @@
+//! Headless app harness for gameplay tests.
+//!
+//! Builds the game on top of [`MinimalPlugins`] with a fixed frame time, so tests can press
+//! keys, step frames and assert on components, resources and triggered events without a
+//! window or renderer.
+
+use std::{marker::PhantomData, time::Duration};
+
+use bevy::{
+    app::Plugins,
+    asset::AssetPlugin,
+    input::{
+        ButtonState, InputPlugin,
+        keyboard::{Key, KeyboardInput, NativeKey},
+    },
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+
+use crate::{
+    buildings::{BuildingPlugin, BuildingRegistry},
+    collision::CollisionPlugin,
+    components::{BuildingEntrance, BuildingId, Interactable, Player, TileGroup, TilePosition},
+    dialogue::ActiveDialogue,
+    entrance::EntrancePlugin,
+    map::MapPlugin,
+    movement::MovementPlugin,
+    player::PlayerPlugin,
+};
+
+/// Simulated time per [`TestApp::step`] frame.
+pub const FRAME_TIME: Duration = Duration::from_millis(50);
+/// Frames a single wait may take before the test fails instead of hanging.
+const MAX_WAIT_FRAMES: usize = 600;
+
+pub struct TestApp {
+    app: App,
+}
+
+impl TestApp {
+    /// The player, grid movement and entrances on top of `map_file`, a `.tmx` or
+    /// `*.world.json` relative to the assets folder. Runs the first frame, so the player is
+    /// already spawned.
+    pub fn new(map_file: &str) -> Self {
+        Self::with_plugins(map_file, ())
+    }
+
+    /// Like [`TestApp::new`], with extra plugins under test.
+    pub fn with_plugins<M>(map_file: &str, plugins: impl Plugins<M>) -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: concat!(env!("CARGO_MANIFEST_DIR"), "/assets").to_string(),
+                ..default()
+            },
+            ImagePlugin::default(),
+            TransformPlugin,
+            InputPlugin,
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME_TIME))
+        // Read by player input; the dialogue UI itself is not part of the harness.
+        .init_resource::<ActiveDialogue>()
+        .register_type::<BuildingEntrance>()
+        .register_type::<BuildingId>()
+        .register_type::<Interactable>()
+        .add_plugins(MapPlugin::headless(map_file))
+        .add_plugins((
+            PlayerPlugin,
+            BuildingPlugin,
+            EntrancePlugin,
+            CollisionPlugin,
+            MovementPlugin,
+        ))
+        .add_plugins(plugins);
+        app.update();
+
+        Self { app }
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    /// Steps until `condition` holds, panicking with `what` if it never does.
+    pub fn wait_until(&mut self, what: &str, mut condition: impl FnMut(&mut World) -> bool) {
+        for _ in 0..MAX_WAIT_FRAMES {
+            if condition(self.world()) {
+                return;
+            }
+            self.app.update();
+        }
+        panic!("Timed out waiting for {}", what);
+    }
+
+    /// Waits for the building registry and for `count` zones to be snapped to the grid.
+    pub fn wait_for_zones(&mut self, count: usize) {
+        self.wait_until("building registry", |world| {
+            world.contains_resource::<BuildingRegistry>()
+        });
+        self.wait_until("zones", |world| {
+            world.query::<&TileGroup>().iter(world).count() >= count
+        });
+    }
+
+    /// Sends a key press through the regular keyboard input path, so it shows up as
+    /// `just_pressed` on the next frame.
+    pub fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Pressed);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Released);
+    }
+
+    fn send_key(&mut self, key_code: KeyCode, state: ButtonState) {
+        self.world().write_message(KeyboardInput {
+            key_code,
+            logical_key: Key::Unidentified(NativeKey::Unidentified),
+            state,
+            text: None,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+    }
+
+    /// Holds `key` until the player has taken `tiles` steps, then lets go.
+    pub fn walk(&mut self, key: KeyCode, tiles: usize) {
+        self.press(key);
+        for _ in 0..tiles {
+            let start = self.player_tile();
+            self.wait_until("player step", |world| player_tile(world) != start);
+        }
+        self.release(key);
+    }
+
+    pub fn player(&mut self) -> Entity {
+        let world = self.world();
+        world
+            .query_filtered::<Entity, With<Player>>()
+            .single(world)
+            .expect("the player should be spawned")
+    }
+
+    pub fn player_tile(&mut self) -> TilePosition {
+        player_tile(self.world())
+    }
+
+    /// Starts counting triggers of `E`; read the count back with [`TestApp::count`].
+    pub fn record<E: Event>(&mut self) {
+        self.app.init_resource::<EventCount<E>>().add_observer(
+            |_trigger: On<E>, mut count: ResMut<EventCount<E>>| {
+                count.count += 1;
+            },
+        );
+    }
+
+    pub fn count<E: Event>(&self) -> usize {
+        self.app.world().resource::<EventCount<E>>().count
+    }
+}
+
+fn player_tile(world: &mut World) -> TilePosition {
+    *world
+        .query_filtered::<&TilePosition, With<Player>>()
+        .single(world)
+        .expect("the player should be spawned")
+}
+
+/// Times `E` was triggered since [`TestApp::record`].
+#[derive(Resource)]
+struct EventCount<E> {
+    count: usize,
+    _marker: PhantomData<fn() -> E>,
+}
+
+impl<E> Default for EventCount<E> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            _marker: PhantomData,
+        }
+    }
+}
@@ -1,159 +1,207 @@
 use core::str;
-use std::time::Duration;
 
 use bevy::prelude::*;
-use bevy_tweening::{lens::UiPositionLens, *};
 
 use crate::{
-    components::{DisplayCurrentTile, Player, PlayerMovementSet, TilePosition},
-    entrance::{PlayerEnteredBuildingEvent, PlayerExitedBuildingEvent},
+    animals::AnimalNeeds,
+    buildings::BuildingRegistry,
+    clock::{DayStarted, GameClock},
+    components::{
+        ActiveMap, BuildingId, DisplayClock, DisplayCurrentTile, DisplayEconomy, Player,
+        PlayerMovementSet, TilePosition,
+    },
+    economy::Economy,
+    entrance::ExitedBuildingEvent,
+    interaction::Interacted,
+    map::{MapId, WorldGraph},
+    notifications::{CategoryMode, Notification, NotificationPriority, Notifications},
+    weather::{WeatherChanged, WeatherState},
 };
 
 pub struct UiPlugin;
 
-pub struct BuildingEnterToast {
-    toast_entity: Entity,
-}
-
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (spawn_tile_display, spawn_instructions))
-            .add_systems(Update, update_current_tile_display.after(PlayerMovementSet));
-
-        // TODO: move this to a separate plugin
+        app.add_systems(
+            Startup,
+            (
+                spawn_tile_display,
+                spawn_clock_display,
+                spawn_economy_display,
+                spawn_instructions,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                update_current_tile_display.after(PlayerMovementSet),
+                update_clock_display,
+                update_economy_display,
+            ),
+        );
 
-        app.add_observer(player_entering_building_observer)
+        app.add_observer(player_interacting_with_building_observer)
             .add_observer(player_exiting_building_observer)
-            .add_observer(despawn_toast_on_completion);
+            .add_observer(day_started_observer)
+            .add_observer(weather_changed_observer);
     }
 }
 
-#[derive(Component)]
-struct BuildingEntranceToastMarker;
-
-#[derive(Component)]
-struct BuildingEntranceToastDismissalMarker;
-
-fn player_exiting_building_observer(
-    trigger: On<PlayerExitedBuildingEvent>,
-    mut commands: Commands,
-    toast_query: Single<(Entity, &Node), With<BuildingEntranceToastMarker>>,
-) {
-    let (toast_identity, toast_node) = *toast_query;
-
-    let start_rect = UiRect {
-        left: toast_node.left,
-        right: toast_node.right,
-        top: toast_node.top,
-        bottom: toast_node.bottom,
-    };
+const BUILDING_ENTRANCE_CATEGORY: &str = "building_entrance";
 
-    let slide_down_tween = Tween::new(
-        EaseFunction::CubicIn,
-        Duration::from_millis(500),
-        UiPositionLens {
-            // Start where the "entering" ended
-            start: start_rect,
-            // End off-screen
-            end: UiRect {
-                bottom: Val::Px(-150.0),
-                left: Val::Px(12.0),
-                ..default()
-            },
-        },
-    );
+/// Distance from the top of the window to the row of toggled panels, below the HUD.
+const PANEL_TOP: f32 = 80.0;
+/// Space between the toggled panels and between them and the window edges.
+const PANEL_GAP: f32 = 12.0;
 
-    commands.entity(toast_identity).insert((
-        TweenAnim::new(slide_down_tween),
-        BuildingEntranceToastDismissalMarker,
-    ));
+/// A third of the row below the HUD, for the panels toggled from the keyboard.
+#[derive(Debug, Clone, Copy)]
+pub enum PanelSlot {
+    Left,
+    Middle,
+    Right,
 }
 
-fn despawn_toast_on_completion(
-    trigger: On<AnimCompletedEvent>,
-    mut commands: Commands,
-    query: Query<Entity, With<BuildingEntranceToastDismissalMarker>>,
-) {
-    if query.get(trigger.anim_entity).is_ok() {
-        commands.entity(trigger.anim_entity).despawn();
+/// The hidden node of a toggled panel in `slot`. Each slot spans a third of the window,
+/// so the achievement, quest and upgrade panels can be open together at any width.
+pub fn panel_node(slot: PanelSlot) -> Node {
+    let third = 100.0 / 3.0;
+    // A full gap at the window edges, half a gap on each side between two panels.
+    let (edge, between) = (px(PANEL_GAP), px(PANEL_GAP / 2.0));
+    let (left, right, margin) = match slot {
+        PanelSlot::Left => (0.0, 2.0 * third, UiRect::new(edge, between, px(0), px(0))),
+        PanelSlot::Middle => (third, third, UiRect::horizontal(between)),
+        PanelSlot::Right => (2.0 * third, 0.0, UiRect::new(between, edge, px(0), px(0))),
+    };
+    Node {
+        position_type: PositionType::Absolute,
+        top: px(PANEL_TOP),
+        left: percent(left),
+        right: percent(right),
+        margin,
+        flex_direction: FlexDirection::Column,
+        row_gap: px(8),
+        padding: UiRect::all(px(12)),
+        display: Display::None,
+        ..default()
     }
 }
 
-fn player_entering_building_observer(
-    trigger: On<PlayerEnteredBuildingEvent>,
-    mut commands: Commands,
-    toast: Option<Single<(Entity, &Node), With<BuildingEntranceToastMarker>>>,
-    asset_server: ResMut<AssetServer>,
+/// Shows the building's details, rendered from its definition data, once the player
+/// interacts with its entrance. Open entrances leading to another map are walked through
+/// instead.
+#[allow(clippy::too_many_arguments)]
+fn player_interacting_with_building_observer(
+    trigger: On<Interacted>,
+    buildings: Query<&BuildingId>,
+    mut notifications: ResMut<Notifications>,
+    asset_server: Res<AssetServer>,
+    registry: Res<BuildingRegistry>,
+    clock: Res<GameClock>,
+    graph: Option<Res<WorldGraph>>,
+    active: Query<&MapId, With<ActiveMap>>,
+    needs: Res<AnimalNeeds>,
 ) {
-    let end = UiRect {
-        bottom: Val::Px(12.0),
-        left: Val::Px(12.0),
-        top: Val::Auto,
-        right: Val::Auto,
+    let Ok(building) = buildings.get(trigger.event().target) else {
+        return;
+    };
+    let Some(definition) = registry.get(&building.id) else {
+        warn!("No building definition for: {}", building.id);
+        return;
     };
 
-    if let Some(toast) = toast {
-        let (toast_entity, toast_node) = *toast;
-        let tween = Tween::new(
-            EaseFunction::CubicOut,
-            Duration::from_millis(500),
-            UiPositionLens {
-                start: UiRect {
-                    left: toast_node.left,
-                    right: toast_node.right,
-                    top: toast_node.top,
-                    bottom: toast_node.bottom,
-                },
-                end,
-            },
-        );
-        commands
-            .entity(toast_entity)
-            .insert(TweenAnim::new(tween))
-            .remove::<BuildingEntranceToastDismissalMarker>();
-
+    let hours = definition.opening_hours;
+    let open = hours.is_open(clock.hour());
+    let linked = graph
+        .zip(active.single().ok())
+        .is_some_and(|(graph, map)| graph.link(&map.0, &building.id).is_some());
+    if open && linked {
         return;
     }
 
-    let tween = Tween::new(
-        EaseFunction::CubicOut,
-        Duration::from_millis(500),
-        UiPositionLens {
-            start: UiRect {
-                bottom: Val::Px(-150.0),
-                left: Val::Px(12.0),
-                top: Val::Auto,
-                right: Val::Auto,
-            },
-            end,
-        },
+    let status = if open { "Open now" } else { "Closed now" };
+    let mut body = format!(
+        "{}\n{} ({:02}:00 - {:02}:00)",
+        definition.description, status, hours.open, hours.close
+    );
+    if let Some(animals) = &definition.animals {
+        let activity = if animals.is_active(clock.phase()) {
+            "active"
+        } else {
+            "resting"
+        };
+        body.push_str(&format!("\n{} are {} now", animals.name, activity));
+        if needs.is_hungry(&building.id) {
+            body.push_str(" and hungry");
+        }
+    }
+    notifications.push(
+        Notification::new(definition.display_name.clone())
+            .with_body(body)
+            .with_icon(asset_server.load(&definition.icon))
+            .with_category(BUILDING_ENTRANCE_CATEGORY, CategoryMode::Replace)
+            .sticky(),
+    );
+}
+
+fn player_exiting_building_observer(
+    trigger: On<ExitedBuildingEvent>,
+    players: Query<(), With<Player>>,
+    mut notifications: ResMut<Notifications>,
+) {
+    if players.contains(trigger.event().actor) {
+        notifications.dismiss_category(BUILDING_ENTRANCE_CATEGORY);
+    }
+}
+
+fn day_started_observer(trigger: On<DayStarted>, mut notifications: ResMut<Notifications>) {
+    notifications.push(
+        Notification::new(format!("Day {}", trigger.event().day))
+            .with_priority(NotificationPriority::Low),
     );
+}
+
+fn weather_changed_observer(trigger: On<WeatherChanged>, mut notifications: ResMut<Notifications>) {
+    notifications.push(
+        Notification::new(format!("Weather: {}", trigger.event().current.name()))
+            .with_priority(NotificationPriority::Low)
+            .with_category("weather", CategoryMode::Replace),
+    );
+}
 
+fn spawn_tile_display(mut commands: Commands) {
     commands.spawn((
+        DisplayCurrentTile,
+        Text::new("Current Tile Position: (0, 0)"),
         Node {
             position_type: PositionType::Absolute,
-            bottom: Val::Px(-150.0),
-            left: Val::Px(12.0),
-            width: Val::Px(150.0),
-            height: Val::Px(100.0),
+            top: px(12),
+            left: px(12),
             ..default()
         },
-        BuildingEntranceToastMarker,
-        TweenAnim::new(tween),
-        ImageNode {
-            image: asset_server.load("enter_building_toast.png"),
+    ));
+}
+
+fn spawn_clock_display(mut commands: Commands) {
+    commands.spawn((
+        DisplayClock,
+        Text::new(""),
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(12),
+            right: px(12),
             ..default()
         },
     ));
 }
 
-fn spawn_tile_display(mut commands: Commands) {
+fn spawn_economy_display(mut commands: Commands) {
     commands.spawn((
-        DisplayCurrentTile,
-        Text::new("Current Tile Position: (0, 0)"),
+        DisplayEconomy,
+        Text::new(""),
         Node {
             position_type: PositionType::Absolute,
-            top: px(12),
+            top: px(48),
             left: px(12),
             ..default()
         },
@@ -205,3 +253,35 @@ fn update_current_tile_display(
         player.x, player.y
     ));
 }
+
+fn update_clock_display(clock: Res<GameClock>, mut display: Single<&mut Text, With<DisplayClock>>) {
+    let mut text = format!(
+        "Day {} {} ({})",
+        clock.day(),
+        clock.time_of_day_text(),
+        clock.phase().name()
+    );
+    if clock.time_scale != 1.0 {
+        text.push_str(&format!(" x{}", clock.time_scale));
+    }
+
+    // Only touch the text when the displayed minute changes.
+    if display.0 != text {
+        display.0 = text;
+    }
+}
+
+fn update_economy_display(
+    economy: Res<Economy>,
+    mut display: Single<&mut Text, With<DisplayEconomy>>,
+) {
+    let text = format!(
+        "Coins: {}  Food: {}/{}",
+        economy.coins(),
+        economy.food(),
+        economy.food_capacity as u32
+    );
+    if display.0 != text {
+        display.0 = text;
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::JsonAssetAppExt,
+    economy::{Cost, Economy},
+    notifications::{Notification, Notifications},
+    ui::{PanelSlot, panel_node},
+};
+
+const UPGRADE_DEFINITIONS_FILE: &str = "data/sanctuary.upgrades.json";
+const TOGGLE_PANEL_KEY: KeyCode = KeyCode::KeyU;
+
+pub struct UpgradePlugin;
+
+impl Plugin for UpgradePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_json_asset::<UpgradeDefinitions>(&["upgrades.json"])
+            .init_resource::<PurchasedUpgrades>()
+            .add_systems(Startup, (load_upgrade_definitions, spawn_upgrade_panel))
+            .add_systems(
+                Update,
+                (
+                    rebuild_upgrade_catalog,
+                    toggle_upgrade_panel,
+                    buy_clicked_upgrades.run_if(resource_exists::<UpgradeCatalog>),
+                    refresh_upgrade_panel.run_if(resource_exists::<UpgradeCatalog>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Upgrades keyed by id, loaded from a `*.upgrades.json` file.
+#[derive(Asset, TypePath, Deserialize)]
+#[serde(transparent)]
+pub struct UpgradeDefinitions(pub HashMap<String, UpgradeDefinition>);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpgradeDefinition {
+    pub display_name: String,
+    pub description: String,
+    pub cost: Cost,
+    /// Upgrades that must be bought first.
+    #[serde(default)]
+    pub requires: Vec<String>,
+    pub effect: UpgradeEffect,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradeEffect {
+    /// Extra food storage capacity.
+    FoodStorage(f64),
+    /// Multiplier on the Nutrition House food prep rate.
+    PrepSpeed(f64),
+    /// Building id that can be placed in build mode once bought.
+    UnlockBuilding(String),
+}
+
+/// Every upgrade, available once the definition file has loaded.
+#[derive(Resource)]
+pub struct UpgradeCatalog {
+    upgrades: HashMap<String, UpgradeDefinition>,
+}
+
+impl UpgradeCatalog {
+    /// Whether `building` can be placed. Buildings no upgrade unlocks are always available.
+    pub fn is_building_unlocked(&self, building: &str, purchased: &PurchasedUpgrades) -> bool {
+        let mut unlocked_by = self
+            .upgrades
+            .iter()
+            .filter(|(_, upgrade)| {
+                matches!(&upgrade.effect, UpgradeEffect::UnlockBuilding(id) if id == building)
+            })
+            .map(|(id, _)| id)
+            .peekable();
+
+        unlocked_by.peek().is_none() || unlocked_by.any(|id| purchased.contains(id))
+    }
+
+    /// Upgrades sorted by cost, then id, for display.
+    fn sorted(&self) -> Vec<(&String, &UpgradeDefinition)> {
+        let mut upgrades: Vec<_> = self.upgrades.iter().collect();
+        upgrades.sort_by_key(|(id, upgrade)| (upgrade.cost.coins, upgrade.cost.food, *id));
+        upgrades
+    }
+}
+
+/// Ids of bought upgrades. Saved with the game.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PurchasedUpgrades(HashSet<String>);
+
+impl PurchasedUpgrades {
+    pub fn contains(&self, id: &str) -> bool {
+        self.0.contains(id)
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.len()
+    }
+}
+
+#[derive(Debug)]
+enum PurchaseError {
+    /// The catalog was reloaded without this upgrade.
+    Unknown,
+    AlreadyOwned,
+    MissingRequirement(String),
+    CannotAfford(Cost),
+}
+
+impl std::fmt::Display for PurchaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PurchaseError::Unknown => write!(f, "No longer available"),
+            PurchaseError::AlreadyOwned => write!(f, "Already owned"),
+            PurchaseError::MissingRequirement(name) => write!(f, "Requires {}", name),
+            PurchaseError::CannotAfford(cost) => write!(f, "Costs {}", cost),
+        }
+    }
+}
+
+/// Checks requirements, pays for the upgrade and applies its effect.
+fn purchase(
+    id: &str,
+    catalog: &UpgradeCatalog,
+    purchased: &mut PurchasedUpgrades,
+    economy: &mut Economy,
+) -> Result<(), PurchaseError> {
+    let Some(upgrade) = catalog.upgrades.get(id) else {
+        return Err(PurchaseError::Unknown);
+    };
+
+    if purchased.contains(id) {
+        return Err(PurchaseError::AlreadyOwned);
+    }
+    if let Some(missing) = upgrade
+        .requires
+        .iter()
+        .find(|required| !purchased.contains(required))
+    {
+        let name = catalog
+            .upgrades
+            .get(missing)
+            .map_or(missing.clone(), |upgrade| upgrade.display_name.clone());
+        return Err(PurchaseError::MissingRequirement(name));
+    }
+    if !economy.try_spend(upgrade.cost) {
+        return Err(PurchaseError::CannotAfford(upgrade.cost));
+    }
+
+    match &upgrade.effect {
+        UpgradeEffect::FoodStorage(extra) => economy.food_capacity += extra,
+        UpgradeEffect::PrepSpeed(multiplier) => economy.prep_per_hour *= multiplier,
+        UpgradeEffect::UnlockBuilding(_) => {}
+    }
+    purchased.0.insert(id.to_string());
+    Ok(())
+}
+
+#[derive(Resource)]
+struct UpgradeDefinitionsHandle(Handle<UpgradeDefinitions>);
+
+#[derive(Component)]
+struct UpgradePanel;
+
+#[derive(Component)]
+struct UpgradeButton(String);
+
+fn load_upgrade_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(UpgradeDefinitionsHandle(
+        asset_server.load(UPGRADE_DEFINITIONS_FILE),
+    ));
+}
+
+fn rebuild_upgrade_catalog(
+    mut commands: Commands,
+    mut asset_events: MessageReader<AssetEvent<UpgradeDefinitions>>,
+    handle: Res<UpgradeDefinitionsHandle>,
+    definitions: Res<Assets<UpgradeDefinitions>>,
+) {
+    let changed = asset_events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. }
+        )
+    });
+    if !changed {
+        return;
+    }
+    let Some(file) = definitions.get(&handle.0) else {
+        return;
+    };
+
+    info!("Upgrade catalog loaded: {} upgrades", file.0.len());
+    commands.insert_resource(UpgradeCatalog {
+        upgrades: file.0.clone(),
+    });
+}
+
+fn spawn_upgrade_panel(mut commands: Commands) {
+    commands.spawn((
+        UpgradePanel,
+        panel_node(PanelSlot::Right),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+    ));
+}
+
+fn toggle_upgrade_panel(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut panel: Single<&mut Node, With<UpgradePanel>>,
+) {
+    if kb_input.just_pressed(TOGGLE_PANEL_KEY) {
+        panel.display = match panel.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+fn buy_clicked_upgrades(
+    buttons: Query<(&Interaction, &UpgradeButton), Changed<Interaction>>,
+    catalog: Res<UpgradeCatalog>,
+    mut purchased: ResMut<PurchasedUpgrades>,
+    mut economy: ResMut<Economy>,
+    mut notifications: ResMut<Notifications>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(upgrade) = catalog.upgrades.get(&button.0) else {
+            warn!("Skipping button for unknown upgrade: {}", button.0);
+            continue;
+        };
+
+        match purchase(&button.0, &catalog, &mut purchased, &mut economy) {
+            Ok(()) => {
+                info!("Upgrade purchased: {}", button.0);
+                notifications.push(
+                    Notification::new(format!("Upgraded: {}", upgrade.display_name))
+                        .with_body(upgrade.description.clone()),
+                );
+            }
+            Err(error) => {
+                notifications.push(
+                    Notification::new(upgrade.display_name.clone()).with_body(error.to_string()),
+                );
+            }
+        }
+    }
+}
+
+/// Rebuilds the panel's rows when it opens or an upgrade is bought.
+fn refresh_upgrade_panel(
+    mut commands: Commands,
+    panel: Single<(Entity, Ref<Node>), With<UpgradePanel>>,
+    catalog: Res<UpgradeCatalog>,
+    purchased: Res<PurchasedUpgrades>,
+) {
+    let (panel, node) = panel.into_inner();
+    if node.display == Display::None
+        || !(node.is_changed() || catalog.is_changed() || purchased.is_changed())
+    {
+        return;
+    }
+
+    commands.entity(panel).despawn_children();
+    commands
+        .entity(panel)
+        .with_child(Text::new("Upgrades (U to close)"));
+
+    for (id, upgrade) in catalog.sorted() {
+        let status = if purchased.contains(id) {
+            "Owned".to_string()
+        } else {
+            upgrade.cost.to_string()
+        };
+
+        commands.entity(panel).with_children(|parent| {
+            parent
+                .spawn((
+                    UpgradeButton(id.clone()),
+                    Button,
+                    Node {
+                        padding: UiRect::all(px(8)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.1)),
+                ))
+                .with_child(Text::new(format!(
+                    "{} ({})\n{}",
+                    upgrade.display_name, status, upgrade.description
+                )));
+        });
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animals::wander_animals,
+    clock::{GameClock, SimulationSeed, tick_clock},
+    collision::CollisionMap,
+    components::{ActorIntentSet, Facing, MoveIntent, Shelter, TileGroup, TilePosition, TileRect},
+    entrance::zone_tile_group,
+    keepers::follow_schedules,
+    map::MapGrids,
+    pathfinding::find_path,
+};
+
+/// In-game minutes between weather rolls.
+const WEATHER_PERIOD_MINUTES: f64 = 4.0 * 60.0;
+/// Upper bound on tiles visited when looking for a way to shelter.
+const MAX_SHELTER_SEARCH: usize = 2048;
+/// Particles sit above every world sprite but below the daylight overlay.
+const PARTICLE_Z: f32 = 900.0;
+/// Half the area around the camera particles are spawned in.
+const PARTICLE_AREA: Vec2 = Vec2::new(600.0, 1000.0);
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Shelter>()
+            .init_resource::<WeatherState>()
+            .add_systems(FixedPreUpdate, roll_weather.after(tick_clock))
+            .add_systems(
+                FixedUpdate,
+                // Overrides schedules and wandering while the weather is bad.
+                seek_shelter
+                    .in_set(ActorIntentSet)
+                    .after(follow_schedules)
+                    .after(wander_animals),
+            )
+            .add_systems(
+                Update,
+                (
+                    snap_shelters,
+                    spawn_weather_particles,
+                    update_weather_particles,
+                ),
+            );
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weather {
+    #[default]
+    Sunny,
+    Rain,
+    Heat,
+    Storm,
+}
+
+impl Weather {
+    pub fn name(self) -> &'static str {
+        match self {
+            Weather::Sunny => "Sunny",
+            Weather::Rain => "Rain",
+            Weather::Heat => "Heat",
+            Weather::Storm => "Storm",
+        }
+    }
+
+    /// Whether actors that [`SeeksShelter`] should head for cover.
+    pub fn seeks_shelter(self) -> bool {
+        matches!(self, Weather::Rain | Weather::Storm)
+    }
+
+    /// Share of the usual visitors, and so donations, that come in this weather.
+    pub fn visitor_factor(self) -> f64 {
+        match self {
+            Weather::Heat => 0.5,
+            _ => 1.0,
+        }
+    }
+
+    /// How much faster animal needs grow in this weather.
+    pub fn need_factor(self) -> f32 {
+        match self {
+            Weather::Heat => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    /// Relative odds of the weather in the next period.
+    fn transitions(self) -> [(Weather, u32); 4] {
+        match self {
+            Weather::Sunny => [
+                (Weather::Sunny, 6),
+                (Weather::Rain, 2),
+                (Weather::Heat, 2),
+                (Weather::Storm, 0),
+            ],
+            Weather::Rain => [
+                (Weather::Sunny, 3),
+                (Weather::Rain, 4),
+                (Weather::Heat, 0),
+                (Weather::Storm, 2),
+            ],
+            Weather::Heat => [
+                (Weather::Sunny, 4),
+                (Weather::Rain, 1),
+                (Weather::Heat, 4),
+                (Weather::Storm, 1),
+            ],
+            Weather::Storm => [
+                (Weather::Sunny, 1),
+                (Weather::Rain, 5),
+                (Weather::Heat, 0),
+                (Weather::Storm, 2),
+            ],
+        }
+    }
+
+    /// The weather of `period`, following this one.
+    ///
+    /// Each roll is seeded from the simulation seed and the period alone, so the same seed
+    /// always produces the same sequence no matter how the clock got there.
+    fn next(self, seed: u64, period: u64) -> Weather {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed ^ period.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let transitions = self.transitions();
+        let total: u32 = transitions.iter().map(|(_, weight)| weight).sum();
+
+        let mut roll = rng.random_range(0..total);
+        for (weather, weight) in transitions {
+            if roll < weight {
+                return weather;
+            }
+            roll -= weight;
+        }
+        self
+    }
+}
+
+/// Current weather and the period it was rolled for. Saved with the game.
+#[derive(Resource, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct WeatherState {
+    pub current: Weather,
+    pub period: u64,
+}
+
+/// Triggered when the weather changes.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WeatherChanged {
+    pub previous: Weather,
+    pub current: Weather,
+}
+
+/// Makes an actor walk to the nearest [`Shelter`] tile while the weather is bad.
+#[derive(Component, Debug, Default)]
+pub struct SeeksShelter {
+    path: VecDeque<TilePosition>,
+}
+
+impl SeeksShelter {
+    /// Tiles left to walk to the chosen shelter, for the debug overlay.
+    #[cfg(debug_assertions)]
+    pub fn path(&self) -> &VecDeque<TilePosition> {
+        &self.path
+    }
+}
+
+#[derive(Component)]
+struct WeatherParticle {
+    velocity: Vec2,
+    lifetime: Timer,
+}
+
+/// Rolls the weather for every period the clock has passed since the last roll.
+fn roll_weather(
+    clock: Res<GameClock>,
+    seed: Res<SimulationSeed>,
+    mut state: ResMut<WeatherState>,
+    mut commands: Commands,
+) {
+    let period = (clock.total_minutes() / WEATHER_PERIOD_MINUTES) as u64;
+    if state.period >= period {
+        return;
+    }
+
+    let previous = state.current;
+    while state.period < period {
+        state.period += 1;
+        state.current = state.current.next(seed.0, state.period);
+    }
+
+    if state.current != previous {
+        info!(
+            "Weather changed: {} -> {}",
+            previous.name(),
+            state.current.name()
+        );
+        commands.trigger(WeatherChanged {
+            previous,
+            current: state.current,
+        });
+    }
+}
+
+fn snap_shelters(
+    mut commands: Commands,
+    grids: MapGrids,
+    query: Query<
+        (
+            Entity,
+            Option<&Transform>,
+            Option<&TiledObject>,
+            Option<&TileRect>,
+        ),
+        (
+            With<Shelter>,
+            Or<(With<TiledObject>, With<TileRect>)>,
+            Without<TileGroup>,
+        ),
+    >,
+) {
+    for (entity, transform, tiled_object, rect) in query.iter() {
+        let label = format!("Shelter {:?}", entity);
+        let grid = grids.of(entity);
+        if let Some(tile_group) = zone_tile_group(&label, grid, transform, tiled_object, rect) {
+            commands.entity(entity).insert(tile_group);
+        }
+    }
+}
+
+fn seek_shelter(
+    weather: Res<WeatherState>,
+    collision_map: Res<CollisionMap>,
+    shelters: Query<&TileGroup, With<Shelter>>,
+    mut seekers: Query<(&mut SeeksShelter, &TilePosition, &mut MoveIntent)>,
+) {
+    for (mut seeker, position, mut intent) in seekers.iter_mut() {
+        if !weather.current.seeks_shelter() {
+            seeker.path.clear();
+            continue;
+        }
+
+        if shelters.iter().any(|shelter| shelter.contains(*position)) {
+            seeker.path.clear();
+            intent.0 = None;
+            continue;
+        }
+
+        while seeker.path.front() == Some(position) {
+            seeker.path.pop_front();
+        }
+
+        if seeker.path.is_empty() {
+            let nearest = shelters
+                .iter()
+                .flat_map(TileGroup::tiles)
+                .filter(|tile| !collision_map.is_blocked(*tile))
+                .min_by_key(|tile| tile.x.abs_diff(position.x) + tile.y.abs_diff(position.y));
+            let Some(goal) = nearest else {
+                continue;
+            };
+            let path = find_path(
+                *position,
+                goal,
+                |tile| collision_map.is_blocked(tile),
+                MAX_SHELTER_SEARCH,
+            );
+            seeker.path = path.unwrap_or_default().into();
+        }
+
+        let next = seeker.path.front().copied();
+        match next.and_then(|next| Facing::towards(*position, next)) {
+            Some(direction) => intent.0 = Some(direction),
+            // Pushed off the path; plan again next frame.
+            None => seeker.path.clear(),
+        }
+    }
+}
+
+/// Spawns rain drops or heat shimmer around the camera. Purely cosmetic, so it uses an
+/// unseeded RNG.
+fn spawn_weather_particles(
+    mut commands: Commands,
+    weather: Res<WeatherState>,
+    camera: Single<&Transform, With<Camera2d>>,
+    time: Res<Time>,
+    mut pending: Local<f32>,
+) {
+    let (per_second, velocity, size, color, lifetime) = match weather.current {
+        Weather::Sunny => return,
+        Weather::Rain => (
+            120.0,
+            Vec2::new(-60.0, -900.0),
+            Vec2::new(2.0, 14.0),
+            Color::srgba(0.6, 0.7, 1.0, 0.6),
+            2.2,
+        ),
+        Weather::Storm => (
+            300.0,
+            Vec2::new(-250.0, -1300.0),
+            Vec2::new(2.0, 18.0),
+            Color::srgba(0.7, 0.75, 0.9, 0.7),
+            1.6,
+        ),
+        Weather::Heat => (
+            15.0,
+            Vec2::new(0.0, 30.0),
+            Vec2::splat(6.0),
+            Color::srgba(1.0, 0.7, 0.3, 0.25),
+            2.0,
+        ),
+    };
+
+    *pending += per_second * time.delta_secs();
+    let mut rng = rand::rng();
+    let center = camera.translation.truncate();
+
+    while *pending >= 1.0 {
+        *pending -= 1.0;
+
+        let offset = Vec2::new(
+            rng.random_range(-PARTICLE_AREA.x..PARTICLE_AREA.x),
+            rng.random_range(-PARTICLE_AREA.y..PARTICLE_AREA.y),
+        );
+        // Falling particles start above the view so they sweep across it.
+        let offset = if velocity.y < 0.0 {
+            offset.with_y(offset.y.abs() + PARTICLE_AREA.y * 0.5)
+        } else {
+            offset
+        };
+
+        commands.spawn((
+            WeatherParticle {
+                velocity,
+                lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+            },
+            Sprite::from_color(color, size),
+            Transform::from_translation((center + offset).extend(PARTICLE_Z)),
+        ));
+    }
+}
+
+fn update_weather_particles(
+    mut commands: Commands,
+    mut particles: Query<(Entity, &mut WeatherParticle, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, mut particle, mut transform) in particles.iter_mut() {
+        particle.lifetime.tick(time.delta());
+        if particle.lifetime.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += (particle.velocity * time.delta_secs()).extend(0.0);
+    }
+}
@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::components::{OcclusionFade, Player, Y_SORT_BASE_Z, Y_SORT_Z_PER_PIXEL, YSort};
+
+pub struct YSortPlugin;
+
+impl Plugin for YSortPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<YSort>()
+            .register_type::<OcclusionFade>()
+            .add_systems(
+                PostUpdate,
+                (y_sort, fade_occluding_buildings).before(TransformSystems::Propagate),
+            );
+    }
+}
+
+/// Global depth for a foot at the given world Y. Lower on screen means closer to the camera.
+fn y_sorted_z(foot_y: f32) -> f32 {
+    Y_SORT_BASE_Z - foot_y * Y_SORT_Z_PER_PIXEL
+}
+
+/// Rewrites the Z of every [`YSort`] entity from its foot Y.
+///
+/// Tiled objects are children of their layer, which already carries a Z offset, so the
+/// parent's global Z is subtracted to land on the same global depth as root entities.
+fn y_sort(
+    mut sorted: Query<(&mut Transform, &YSort, Option<&ChildOf>)>,
+    parents: Query<&GlobalTransform>,
+) {
+    for (mut transform, y_sort, child_of) in sorted.iter_mut() {
+        let parent_translation = child_of
+            .and_then(|child_of| parents.get(child_of.parent()).ok())
+            .map(GlobalTransform::translation)
+            .unwrap_or(Vec3::ZERO);
+
+        let foot_y = parent_translation.y + transform.translation.y + y_sort.foot_offset;
+        let z = y_sorted_z(foot_y) - parent_translation.z;
+
+        // Avoid touching the transform (and triggering propagation) when nothing changed.
+        if transform.translation.z != z {
+            transform.translation.z = z;
+        }
+    }
+}
+
+/// Fades buildings the player is standing behind.
+fn fade_occluding_buildings(
+    player: Single<(&Transform, &YSort), With<Player>>,
+    buildings: Query<(Entity, &GlobalTransform, &TiledObject, &YSort, &OcclusionFade)>,
+    children: Query<&Children>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    let (player_transform, player_y_sort) = *player;
+    let player_foot = Vec2::new(
+        player_transform.translation.x,
+        player_transform.translation.y + player_y_sort.foot_offset,
+    );
+
+    for (entity, global_transform, tiled_object, y_sort, fade) in buildings.iter() {
+        let (TiledObject::Tile { width, height } | TiledObject::Rectangle { width, height }) =
+            tiled_object
+        else {
+            continue;
+        };
+
+        let origin = global_transform.translation();
+        let foot_y = origin.y + y_sort.foot_offset;
+
+        let occluded = player_foot.x >= origin.x
+            && player_foot.x <= origin.x + width
+            && player_foot.y > foot_y
+            && player_foot.y < origin.y + height;
+        let alpha = if occluded { fade.alpha } else { 1.0 };
+
+        // The visual may live on the object itself or on one of its children.
+        for sprite_entity in std::iter::once(entity).chain(children.iter_descendants(entity)) {
+            let Ok(mut sprite) = sprites.get_mut(sprite_entity) else {
+                continue;
+            };
+            if sprite.color.alpha() != alpha {
+                sprite.color.set_alpha(alpha);
+            }
+        }
+    }
+}